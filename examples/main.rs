@@ -3,7 +3,6 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
-extern crate bzip2;
 extern crate parse_mediawiki_dump_reboot;
 
 fn main() {
@@ -13,30 +12,29 @@ fn main() {
         std::process::exit(1);
     }
     let path = args.nth(1).unwrap();
-    let file = match std::fs::File::open(&path) {
+    let source = match std::fs::File::open(&path)
+        .and_then(|file| parse_mediawiki_dump_reboot::decompress(std::io::BufReader::new(file)))
+    {
         Err(error) => {
             eprintln!("Failed to open input file: {}", error);
             std::process::exit(1);
         }
-        Ok(file) => std::io::BufReader::new(file),
+        Ok(source) => source,
     };
-    if path.ends_with(".bz2") {
-        parse(std::io::BufReader::new(
-            bzip2::bufread::MultiBzDecoder::new(file),
-        ));
-    } else {
-        parse(file);
-    }
-}
-
-fn parse(source: impl std::io::BufRead) {
-    for result in parse_mediawiki_dump_reboot::parse(source) {
+    let parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .recovery(true)
+        .parse(source);
+    let mut failed = false;
+    for result in parser {
         match result {
             Err(error) => {
                 eprintln!("Error: {}", error);
-                std::process::exit(1);
+                failed = true;
             }
             Ok(page) => eprintln!("{:#?}", page),
         }
     }
+    if failed {
+        std::process::exit(1);
+    }
 }
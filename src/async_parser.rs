@@ -0,0 +1,106 @@
+//! Parse dumps from asynchronous streams.
+
+use crate::{
+    Error, Input, Page, Parser, ParserBuilder, Source, create_parser, decode_page, next, recover,
+};
+use quick_xml::{NsReader, events::Event, name::ResolveResult};
+use std::{
+    borrow::Cow,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// Asynchronous stream read by a parser, distinguished from synchronous streams.
+pub(crate) struct Async<R>(R);
+
+/// Parser working as a source of pages from an asynchronous stream.
+///
+/// Created by `parse_async` or `ParserBuilder::parse_async`. The dump is parsed by the same code as with `Parser`, waiting for the stream whenever more of it is needed, so that pages, options and errors, including their locations, are the same as when parsing synchronously. No thread is used, and the stream is only read while a page is awaited.
+pub struct AsyncParser<R> {
+    parser: Parser<Async<R>>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncParser<R> {
+    /// Turns the parser into a stream of pages.
+    pub fn into_stream(self) -> impl futures::Stream<Item = Result<Page, Error>> {
+        futures::stream::unfold(self, |mut parser| async move {
+            let page = parser.next_page().await?;
+            Some((page, parser))
+        })
+    }
+
+    /// Parses the next page.
+    ///
+    /// Returns `None` when the end of the dump is reached, and after an error the parser can't continue from. Parsing continues after `Error::Escape`, `Error::Sha1Mismatch` and `Error::Skipped`.
+    pub async fn next_page(&mut self) -> Option<Result<Page, Error>> {
+        let parser = &mut self.parser;
+        Some(match next(parser).await {
+            Err(error) => Err(recover(parser, error).await),
+            Ok(page) => decode_page(parser, page?, Cow::into_owned),
+        })
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Input for Async<R> {
+    fn consume(&mut self, amount: usize) {
+        Pin::new(&mut self.0).consume(amount);
+    }
+
+    fn poll_fill_buf(&mut self, context: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        Pin::new(&mut self.0).poll_fill_buf(context)
+    }
+
+    fn read_event<'a, 'b>(
+        reader: &'a mut NsReader<Source<Self>>,
+        buffer: &'b mut Vec<u8>,
+    ) -> impl Future<Output = Result<(ResolveResult<'a>, Event<'b>), quick_xml::Error>> {
+        reader.read_resolved_event_into_async(buffer)
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for Source<Async<R>> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let source = self.get_mut();
+        let available = std::task::ready!(source.poll_fill_buf(context))?;
+        let length = available.len().min(buf.remaining());
+        buf.put_slice(&available[..length]);
+        source.consume(length);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for Source<Async<R>> {
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        self.get_mut().consume(amount);
+    }
+
+    fn poll_fill_buf(
+        self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<std::io::Result<&[u8]>> {
+        self.get_mut().poll_fill_buf(context)
+    }
+}
+
+impl ParserBuilder {
+    /// Creates a parser for an asynchronous stream with the options of the builder.
+    ///
+    /// See `parse_async`.
+    pub fn parse_async<R: AsyncBufRead + Unpin>(self, source: R) -> AsyncParser<R> {
+        AsyncParser {
+            parser: create_parser(self, Async(source)),
+        }
+    }
+}
+
+/// Creates a parser for an asynchronous stream.
+///
+/// The stream is parsed as an XML dump exported from Mediawiki. Pages are obtained from `AsyncParser::next_page`, or from the stream returned by `AsyncParser::into_stream`.
+pub fn parse_async<R: AsyncBufRead + Unpin>(source: R) -> AsyncParser<R> {
+    ParserBuilder::new().parse_async(source)
+}
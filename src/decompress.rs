@@ -0,0 +1,93 @@
+//! Detect the compression of dumps and decompress them.
+
+use std::io::BufRead;
+
+/// Compression format of a dump.
+///
+/// Detected from the magic bytes at the start of the file, regardless of the file name.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Compression {
+    /// bzip2, as used by the Wikimedia dumps. Decompressing requires the cargo feature `bzip2`.
+    Bzip2,
+
+    /// gzip. Decompressing requires the cargo feature `gzip`.
+    Gzip,
+
+    /// Not compressed.
+    None,
+
+    /// xz. Decompressing requires the cargo feature `xz`.
+    Xz,
+
+    /// Zstandard. Decompressing requires the cargo feature `zstd`.
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression format from the first bytes of a file.
+    ///
+    /// Returns `Compression::None` if the bytes don't start with the magic bytes of any supported format.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0]) {
+            Compression::Xz
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(match self {
+            Compression::Bzip2 => "bzip2",
+            Compression::Gzip => "gzip",
+            Compression::None => "none",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        })
+    }
+}
+
+/// Decompresses a stream in any supported compression format.
+///
+/// The format is detected from the magic bytes at the start of the stream, without consuming them. Streams that are not compressed are returned as they are. Streams made of several concatenated compressed streams, such as the multistream dumps from Wikimedia, are decompressed in full.
+///
+/// # Errors
+///
+/// Fails if reading the start of the stream fails, or with the error kind `Unsupported` if the cargo feature required to decompress the detected format is not enabled.
+pub fn decompress<R: BufRead + Send + 'static>(
+    mut source: R,
+) -> std::io::Result<Box<dyn BufRead + Send>> {
+    Ok(match Compression::detect(source.fill_buf()?) {
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(std::io::BufReader::new(
+            bzip2::bufread::MultiBzDecoder::new(source),
+        )),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(std::io::BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(source),
+        )),
+        Compression::None => Box::new(source),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(std::io::BufReader::new(
+            xz2::bufread::XzDecoder::new_multi_decoder(source),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(std::io::BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(source)?,
+        )),
+        #[allow(unreachable_patterns)]
+        compression => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Decompressing {compression} requires the cargo feature `{compression}`"),
+            ));
+        }
+    })
+}
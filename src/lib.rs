@@ -13,43 +13,60 @@
 //!
 //! # Limitations
 //!
-//! This module only parses dumps containing only one revision of each page. This is what you get from the page `Special:Export` when enabling the option “Include only the current revision, not the full history”, as well as what you get from the Wikimedia dumps with file names ending with `-pages-articles.xml.bz2`.
+//! The function `parse` only parses dumps containing only one revision of each page. This is what you get from the page `Special:Export` when enabling the option “Include only the current revision, not the full history”, as well as what you get from the Wikimedia dumps with file names ending with `-pages-articles.xml.bz2`.
 //!
-//! This module ignores the `siteinfo` element, every child element of the `page` element except `ns`, `revision` and `title`, and every element inside the `revision` element except `format`, `model` and `text`.
+//! Dumps containing the full history of each page, such as the Wikimedia dumps with file names containing `-pages-meta-history`, are parsed with the function `parse_history`. Each page then provides its revisions through an iterator that parses one revision at a time, so that pages with a very long history don't have to be held in memory at once. The text of a revision can also be read as a stream with `PageHistory::next_streamed`, so that very large texts don't have to be held in memory either.
+//!
+//! Pages and revisions normally own their text. To avoid copying the text of every page, `Parser::next_borrowed` and `PageHistory::next_borrowed` return them borrowing the text from the parser until the next call, copying only text that contains entities. Alternatively `Parser::parse_into` parses each page into an existing `Page`, reusing the memory that holds its text.
+//!
+//! Pages can be written back to a dump with `Writer`, for example to produce a dump containing only some of the pages.
+//!
+//! Options such as a condition on the pages to return, limits on the size of the content, continuing with the next page after an error or parsing dumps that don't declare the namespace of the schema are set with `ParserBuilder`.
+//!
+//! This module ignores every child element of the `page` element except `id`, `ns`, `redirect`, `restrictions`, `revision` and `title`, and every element inside the `revision` element except `comment`, `content`, `contributor`, `format`, `id`, `minor`, `model`, `origin`, `parentid`, `sha1`, `text` and `timestamp`. The `siteinfo` element is available from `Parser::site_info`.
+//!
+//! Dumps conforming to any published version of the [export schema](https://www.mediawiki.org/xml/), from 0.1 to 0.11, are supported. The detected version is available from `Parser::schema_version`.
 //!
 //! Until there is a real use case that justifies going beyond these limitations, they will remain in order to avoid premature design driven by imagined requirements.
 //!
+//! # Features
+//!
+//! - `async`: Parses dumps from a `tokio::io::AsyncBufRead` with `parse_async` and `ParserBuilder::parse_async`, providing the pages as a `futures::Stream`.
+//! - `bzip2`: Decompresses bzip2 compressed dumps with `open` and `decompress`, and parses single pages from multistream dumps with `MultistreamDump`.
+//! - `chrono`: Converts `Timestamp` into `chrono::DateTime<chrono::Utc>`.
+//! - `gzip`: Decompresses gzip compressed dumps with `open` and `decompress`.
+//! - `rayon`: Parses multistream dumps on all cores with `MultistreamDump::par_pages`. Implies `bzip2`.
+//! - `time`: Converts `Timestamp` into `time::OffsetDateTime`.
+//! - `xz`: Decompresses xz compressed dumps with `open` and `decompress`.
+//! - `zstd`: Decompresses Zstandard compressed dumps with `open` and `decompress`.
+//!
 //! # Examples
 //!
-//! Parse a bzip2 compressed file and distinguish ordinary articles from other pages. A running example with complete error handling is available in the `examples` folder.
+//! Parse a bzip2 compressed file, with the cargo feature `bzip2` enabled, and distinguish ordinary articles from other pages. A running example with complete error handling is available in the `examples` folder.
 //!
 //! ```rust,no_run
-//! extern crate bzip2;
 //! extern crate parse_mediawiki_dump_reboot;
 //! use parse_mediawiki_dump_reboot::schema::Namespace;
 //!
 //! fn main() {
-//!     let file = std::fs::File::open("example.xml.bz2").unwrap();
-//!     let file = std::io::BufReader::new(file);
-//!     let file = bzip2::bufread::MultiBzDecoder::new(file);
-//!     let file = std::io::BufReader::new(file);
-//!     for result in parse_mediawiki_dump_reboot::parse(file) {
+//!     let parser = parse_mediawiki_dump_reboot::open("example.xml.bz2").unwrap();
+//!     for result in parser {
 //!         match result {
 //!             Err(error) => {
 //!                 eprintln!("Error: {}", error);
 //!                 break;
 //!             }
-//!             Ok(page) => if page.namespace == Namespace::Main && match &page.format {
+//!             Ok(page) => if page.namespace == Namespace::Main && match &page.revision.format {
 //!                 None => false,
 //!                 Some(format) => format == "text/x-wiki"
-//!             } && match &page.model {
+//!             } && match &page.revision.model {
 //!                 None => false,
 //!                 Some(model) => model == "wikitext"
 //!             } {
 //!                 println!(
 //!                     "The page {title:?} is an ordinary article with byte length {length}.",
 //!                     title = page.title,
-//!                     length = page.text.len()
+//!                     length = page.revision.text.len()
 //!                 );
 //!             } else {
 //!                 println!("The page {:?} has something special to it.", page.title);
@@ -63,322 +80,2814 @@
 
 extern crate quick_xml;
 
+#[cfg(feature = "async")]
+mod async_parser;
+mod decompress;
+#[cfg(feature = "bzip2")]
+mod multistream;
 pub mod schema;
-use quick_xml::{NsReader, events::Event, name::ResolveResult};
+mod timestamp;
+mod writer;
+#[cfg(feature = "async")]
+pub use async_parser::{AsyncParser, parse_async};
+pub use decompress::{Compression, decompress};
+#[cfg(feature = "rayon")]
+pub use multistream::ParallelPages;
+#[cfg(feature = "bzip2")]
+pub use multistream::{MultistreamDump, MultistreamIndex};
+use quick_xml::{
+    NsReader,
+    encoding::EncodingError,
+    events::{BytesStart, Event},
+    name::ResolveResult,
+};
 use schema::Namespace;
-use std::io::BufRead;
+use std::{
+    borrow::Cow,
+    io::BufRead,
+    ops::Range,
+    path::Path,
+    task::{Context, Poll, Waker},
+};
+pub use timestamp::{Timestamp, TimestampError};
+pub use writer::Writer;
+
+type Filter = Box<dyn FnMut(&PageHeader<&str>) -> bool + Send>;
+
+type RawPageHeader = PageHeader<Range<usize>>;
+
+/// Namespaces with their canonical names, used to find the namespace of titles in dumps without a table of namespaces.
+static CANONICAL_NAMESPACES: std::sync::LazyLock<Namespaces> =
+    std::sync::LazyLock::new(|| Namespaces {
+        namespaces: (-2..=15)
+            .map(|key| NamespaceInfo {
+                aliases: Namespace::from(key)
+                    .canonical_names()
+                    .iter()
+                    .map(|alias| alias.to_string())
+                    .collect(),
+                case: None,
+                key,
+                name: String::new(),
+            })
+            .collect(),
+    });
+
+enum ContentChildElement {
+    Format,
+    Model,
+    Origin,
+    Role,
+    Text(Option<Range<usize>>, bool),
+    Unknown,
+}
+
+enum ContributorChildElement {
+    Id,
+    Ip,
+    Username,
+    Unknown,
+}
+
 enum PageChildElement {
+    Id,
     Ns,
+    Redirect(Range<usize>),
+    Restrictions,
     Revision,
     Title,
     Unknown,
 }
 
 enum RevisionChildElement {
+    Comment(bool),
+    Content,
+    Contributor(bool),
     Format,
+    Id,
+    Minor,
     Model,
-    Text,
+    Origin,
+    ParentId,
+    Sha1,
+    Text(Option<Range<usize>>, bool),
+    Timestamp,
     Unknown,
 }
 
+enum SiteInfoChildElement {
+    Base,
+    Case,
+    DbName,
+    Generator,
+    Namespaces,
+    SiteName,
+    Unknown,
+}
+
+#[derive(Default)]
+struct Options {
+    filter: Option<Filter>,
+    keep_unknown_entities: bool,
+    lenient_namespaces: bool,
+    max_revisions: Option<usize>,
+    max_tag_length: Option<usize>,
+    max_text_length: Option<usize>,
+    max_title_length: Option<usize>,
+    recovery: bool,
+    skip_text: bool,
+    trim_text: bool,
+    verify_sha1: bool,
+}
+
+struct Source<R> {
+    line: u64,
+    line_start: u64,
+    offset: u64,
+    prefix: Vec<u8>,
+    reader: Option<R>,
+    tag_limit: Option<u64>,
+}
+
+struct TextLocation {
+    line: u64,
+    line_start: u64,
+    offset: u64,
+    path: Range<usize>,
+    start: usize,
+}
+
+trait ChildElement: Sized {
+    const UNKNOWN: Self;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error>;
+}
+
+/// Stream a parser reads from, either synchronously or asynchronously.
+///
+/// Parsing is written once as futures, which are completed at once for a synchronous stream, since reading it blocks instead of waiting.
+trait Input: Sized {
+    fn consume(&mut self, amount: usize);
+
+    fn poll_fill_buf(&mut self, context: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>>;
+
+    fn read_event<'a, 'b>(
+        reader: &'a mut NsReader<Source<Self>>,
+        buffer: &'b mut Vec<u8>,
+    ) -> impl Future<Output = Result<(ResolveResult<'a>, Event<'b>), quick_xml::Error>>;
+}
+
+/// Case sensitivity of the first letter of titles.
+///
+/// Parsed from the `case` element in the `siteinfo` element and from the `case` attribute of the `namespace` element. Values not listed here are ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Case {
+    /// All letters of titles are case sensitive.
+    ///
+    /// Parsed from the value `case-sensitive`.
+    CaseSensitive,
+
+    /// All letters of titles are case insensitive.
+    ///
+    /// Parsed from the value `case-insensitive`.
+    CaseInsensitive,
+
+    /// The first letter of titles is always capitalized, the other letters are case sensitive.
+    ///
+    /// Parsed from the value `first-letter`.
+    FirstLetter,
+}
+
+/// Version of the [export schema](https://www.mediawiki.org/xml/) a dump conforms to.
+///
+/// Detected from the namespace of the `mediawiki` element, such as `http://www.mediawiki.org/xml/export-0.10/`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SchemaVersion {
+    /// Version 0.1.
+    V0_1,
+    /// Version 0.2.
+    V0_2,
+    /// Version 0.3.
+    V0_3,
+    /// Version 0.4.
+    V0_4,
+    /// Version 0.5.
+    V0_5,
+    /// Version 0.6.
+    V0_6,
+    /// Version 0.7.
+    V0_7,
+    /// Version 0.8.
+    V0_8,
+    /// Version 0.9.
+    V0_9,
+    /// Version 0.10.
+    V0_10,
+    /// Version 0.11.
+    V0_11,
+}
+
+/// User who made a revision.
+///
+/// Parsed from the `contributor` element. Registered users are identified by `username` and `id`, anonymous users by `ip`, and hidden users by `deleted`.
+#[derive(Debug, Default)]
+pub struct Contributor<S = String> {
+    /// Whether the user has been hidden.
+    ///
+    /// Parsed from the `deleted` attribute. When the user has been hidden, the other fields are `None`.
+    pub deleted: bool,
+
+    /// The identifier of the user if any.
+    ///
+    /// Parsed from the text content of the `id` element. `None` if the element is not present.
+    pub id: Option<u64>,
+
+    /// The IP address of the user if any.
+    ///
+    /// Parsed from the text content of the `ip` element. `None` if the element is not present.
+    pub ip: Option<S>,
+
+    /// The name of the user if any.
+    ///
+    /// Parsed from the text content of the `username` element. `None` if the element is not present.
+    pub username: Option<S>,
+}
+
 #[derive(Debug)]
 /// The error type for `Parser`.
 pub enum Error {
+    /// An entity in text content can't be unescaped.
+    ///
+    /// Indicates the error and the location in the stream of the element containing the text. For the text of pages and revisions, the error is reported at their end, where parsing can continue. Not reported for entities kept as they are with `ParserBuilder::keep_unknown_entities`.
+    Escape(Box<quick_xml::escape::EscapeError>, Location),
+
     /// Format not matching expectations.
     ///
-    /// Indicates the position in the stream.
-    Format(usize),
+    /// Indicates the location in the stream.
+    Format(Location),
+
+    /// The dump exceeds a limit set with `ParserBuilder::limit`.
+    ///
+    /// Indicates the limit and the location in the stream.
+    LimitExceeded(Limit, Location),
 
     /// The source contains a feature not supported by the parser.
     ///
-    /// In particular, this means a `page` element contains more than one `revision` element.
-    NotSupported(usize),
+    /// In particular, this means a `page` element contains more than one `revision` element when not parsing with `parse_history`.
+    NotSupported(Location),
+
+    /// The text of a revision doesn't match the SHA-1 hash in its `sha1` element.
+    ///
+    /// Only reported when verification is enabled with `ParserBuilder::verify_sha1`. Indicates the identifier of the revision if any and the location in the stream of the `sha1` element, including the page. The error is reported at the end of the page or revision, where parsing can continue.
+    Sha1Mismatch(Option<u64>, Location),
 
-    /// Error from the XML reader.
-    XmlReader(quick_xml::Error),
+    /// Part of the stream was skipped after an error.
+    ///
+    /// Only reported when recovery is enabled with `ParserBuilder::recovery`. Indicates the error and the range of offsets in bytes of the part that was skipped, from the location of the error to the start of the next `page` element or the end of the stream.
+    Skipped(Box<Error>, Range<u64>),
+
+    /// The text content of a `timestamp` element is not a valid timestamp.
+    ///
+    /// Indicates the location in the stream.
+    Timestamp(Location),
+
+    /// Error from the XML reader, including errors reading the source.
+    ///
+    /// Indicates the location in the stream. For errors reading a stream before it is parsed, such as the index or the compressed streams of a multistream dump, the location is `Location::default()`.
+    XmlReader(Box<quick_xml::Error>, Location),
+}
+
+/// Parser working as a source of pages with their full history.
+///
+/// Created by `parse_history`. Each call to `next_page` parses the beginning of the next page and returns a `PageHistory` iterating over the revisions of that page.
+pub struct HistoryParser<R> {
+    in_page: bool,
+    parser: Parser<R>,
+    revision_count: usize,
+    revision_pending: bool,
+    revision_streamed: bool,
+    text_pending: bool,
+}
+
+/// Limit on the size of the content of a dump.
+///
+/// Set with `ParserBuilder::limit` and reported by `Error::LimitExceeded`. Lengths are measured in bytes of the source, before entities are unescaped.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Limit {
+    /// The maximum number of revisions of a page when parsing with `parse_history`.
+    Revisions,
+
+    /// The maximum length of a tag, including its attributes, such as the title of a redirect.
+    ///
+    /// Also applies to comments, processing instructions and CDATA sections.
+    Tag,
+
+    /// The maximum length of the text of a revision or slot, and of the text content of the other elements except the title.
+    ///
+    /// Text that is skipped, such as the text content of unknown elements and the text between elements, is not limited, as it's never held in memory.
+    Text,
+
+    /// The maximum length of the title of a page.
+    Title,
+}
+
+/// Location in a stream where an error occurred.
+///
+/// Carried by the variants of `Error` reported by the parser.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Location {
+    /// The column in bytes, starting at 1.
+    pub column: u64,
+
+    /// The line, starting at 1.
+    pub line: u64,
+
+    /// The position in bytes from the beginning of the stream.
+    pub offset: u64,
+
+    /// The identifier of the page being parsed if any.
+    ///
+    /// `None` if the error did not occur in a `page` element or the identifier of the page was not parsed yet.
+    pub page_id: Option<u64>,
+
+    /// The title of the page being parsed if any.
+    ///
+    /// `None` if the error did not occur in a `page` element or the title of the page was not parsed yet.
+    pub page_title: Option<String>,
+
+    /// The path of the element being parsed, such as `mediawiki/page/revision/text`.
+    ///
+    /// Made of the local names of the element and its ancestors. Empty if the error occurred outside of the root element.
+    pub path: String,
+}
+
+/// Namespace defined by the wiki.
+///
+/// Parsed from the `namespace` element in the `namespaces` element.
+#[derive(Clone, Debug)]
+pub struct NamespaceInfo {
+    /// Other names of the namespace that are recognized in titles.
+    ///
+    /// These are the names from `Namespace::canonical_names` that differ from the localized name, such as `User` for a namespace named `Utilisateur`.
+    pub aliases: Vec<String>,
+
+    /// The case sensitivity of titles in the namespace if specified.
+    ///
+    /// Parsed from the `case` attribute. `None` if the attribute is not present or its value is not recognized.
+    pub case: Option<Case>,
+
+    /// The numeric identifier of the namespace.
+    ///
+    /// Parsed from the `key` attribute.
+    pub key: i32,
+
+    /// The localized name of the namespace.
+    ///
+    /// Parsed from the text content of the element. Empty for the main namespace.
+    pub name: String,
+}
+
+/// Table of the namespaces defined by the wiki.
+///
+/// Parsed from the `namespaces` element in the `siteinfo` element.
+#[derive(Clone, Debug, Default)]
+pub struct Namespaces {
+    namespaces: Vec<NamespaceInfo>,
 }
 
 /// Parsed page.
 ///
 /// Parsed from the `page` element.
 ///
-/// Although the `format` and `model` elements are defined as mandatory in the [schema](https://www.mediawiki.org/xml/export-0.10.xsd), previous versions of the schema don't contain them. Therefore the corresponding fields can be `None`.
-#[derive(Debug)]
-pub struct Page {
-    /// The format of the revision if any.
+/// The text fields are `String` for pages returned by the iterator over `Parser`. For pages returned by `Parser::next_borrowed` they are `Cow<str>`, borrowing text that contains no entities from the parser instead of copying it.
+#[derive(Debug, Default)]
+pub struct Page<S = String> {
+    /// The identifier of the page if any.
     ///
-    /// Parsed from the text content of the `format` element in the `revision` element. `None` if the element is not present.
+    /// Parsed from the text content of the `id` element in the `page` element. `None` if the element is not present.
+    pub id: Option<u64>,
+
+    /// The namespace of the page.
     ///
-    /// For ordinary articles the format is `text/x-wiki`.
-    pub format: Option<String>,
+    /// Parsed from the text content of the `ns` element in the `page` element. Dumps conforming to older versions of the schema don't contain the element, in which case the namespace is found from the prefix of the title using `SiteInfo::namespaces`, or using `Namespace::canonical_names` if the dump has no table of namespaces.
+    ///
+    /// For ordinary articles the namespace is 0.
+    pub namespace: Namespace,
 
-    /// The model of the revision if any.
+    /// The title of the page this page redirects to if it is a redirect.
+    ///
+    /// Parsed from the `title` attribute of the `redirect` element in the `page` element. `None` if the element is not present. Empty if the element has no `title` attribute, which is the case in dumps from older versions of Mediawiki.
+    pub redirect: Option<S>,
+
+    /// The restrictions on editing and moving the page if any.
     ///
-    /// Parsed from the text content of the `model` element in the `revision` element. `None` if the element is not present.
+    /// Parsed from the text content of the `restrictions` element in the `page` element. `None` if the element is not present.
+    pub restrictions: Option<S>,
+
+    /// The revision of the page.
     ///
-    /// For ordinary articles the model is `wikitext`.
-    pub model: Option<String>,
+    /// Parsed from the `revision` element in the `page` element.
+    pub revision: Revision<S>,
+
+    /// The title of the page.
+    ///
+    /// Parsed from the text content of the `title` element in the `page` element.
+    pub title: S,
+}
+
+/// Header of a page.
+///
+/// Parsed from the child elements of the `page` element preceding the first `revision` element. Given to the condition set with `ParserBuilder::filter` before any revision of the page is parsed.
+#[derive(Debug)]
+pub struct PageHeader<S = String> {
+    /// The identifier of the page if any.
+    ///
+    /// Parsed from the text content of the `id` element in the `page` element. `None` if the element is not present.
+    pub id: Option<u64>,
 
     /// The namespace of the page.
     ///
-    /// Parsed from the text content of the `ns` element in the `page` element.
+    /// Parsed from the text content of the `ns` element in the `page` element. Dumps conforming to older versions of the schema don't contain the element, in which case the namespace is found from the prefix of the title using `SiteInfo::namespaces`, or using `Namespace::canonical_names` if the dump has no table of namespaces.
+    pub namespace: Namespace,
+
+    /// The title of the page this page redirects to if it is a redirect.
     ///
-    /// For ordinary articles the namespace is 0.
+    /// Parsed from the `title` attribute of the `redirect` element in the `page` element. `None` if the element is not present. Empty if the element has no `title` attribute, which is the case in dumps from older versions of Mediawiki.
+    pub redirect: Option<S>,
+
+    /// The restrictions on editing and moving the page if any.
+    ///
+    /// Parsed from the text content of the `restrictions` element in the `page` element. `None` if the element is not present.
+    pub restrictions: Option<S>,
+
+    /// The title of the page.
+    ///
+    /// Parsed from the text content of the `title` element in the `page` element.
+    pub title: S,
+}
+
+/// Page being parsed by `HistoryParser`, working as an iterator over its revisions.
+///
+/// Revisions are parsed one at a time, in the order they appear in the dump. Revisions not consumed before the next call to `HistoryParser::next_page` are skipped without being parsed.
+pub struct PageHistory<'a, R> {
+    /// The identifier of the page if any.
+    ///
+    /// Parsed from the text content of the `id` element in the `page` element. `None` if the element is not present.
+    pub id: Option<u64>,
+
+    /// The namespace of the page.
+    ///
+    /// Parsed from the text content of the `ns` element in the `page` element. Dumps conforming to older versions of the schema don't contain the element, in which case the namespace is found from the prefix of the title using `SiteInfo::namespaces`, or using `Namespace::canonical_names` if the dump has no table of namespaces.
     pub namespace: Namespace,
 
-    /// The text of the revision.
+    /// The title of the page this page redirects to if it is a redirect.
+    ///
+    /// Parsed from the `title` attribute of the `redirect` element in the `page` element. `None` if the element is not present. Empty if the element has no `title` attribute, which is the case in dumps from older versions of Mediawiki.
+    pub redirect: Option<String>,
+
+    /// The restrictions on editing and moving the page if any.
     ///
-    /// Parsed from the text content of the `text` element in the `revision` element.
-    pub text: String,
+    /// Parsed from the text content of the `restrictions` element in the `page` element. `None` if the element is not present.
+    pub restrictions: Option<String>,
 
     /// The title of the page.
     ///
     /// Parsed from the text content of the `title` element in the `page` element.
     pub title: String,
+
+    parser: &'a mut HistoryParser<R>,
 }
 
 /// Parser working as an iterator over pages.
-pub struct Parser<R: BufRead> {
+pub struct Parser<R> {
     buffer: Vec<u8>,
-    reader: NsReader<R>,
-    started: bool,
+    finished: bool,
+    options: Options,
+    page_id: Option<u64>,
+    page_pending: bool,
+    page_title: Vec<u8>,
+    path: String,
+    reader: NsReader<Source<R>>,
+    root: Vec<u8>,
+    schema_version: Option<SchemaVersion>,
+    site_info: Option<SiteInfo>,
+    text_buffer: Vec<u8>,
+    text_locations: Vec<TextLocation>,
+    text_paths: String,
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Error::Format(position) => write!(formatter, "Invalid format at position {}", position),
-            Error::NotSupported(position) => write!(
-                formatter,
-                "The element at position {} is not supported",
-                position
-            ),
-            Error::XmlReader(error) => error.fmt(formatter),
-        }
-    }
+/// Builder collecting the options of a parser.
+///
+/// Created by `ParserBuilder::new` with the same options as a parser created by `parse`. The parser is created by `ParserBuilder::parse` or, for dumps containing the full history of each page, `ParserBuilder::parse_history`, after which its options can't be changed.
+#[derive(Default)]
+pub struct ParserBuilder {
+    options: Options,
 }
 
-impl From<quick_xml::Error> for Error {
-    fn from(value: quick_xml::Error) -> Self {
-        Error::XmlReader(value)
-    }
+/// Parsed revision.
+///
+/// Parsed from the `revision` element.
+///
+/// Although the `format`, `model`, `sha1` and `timestamp` elements are defined as mandatory in the [schema](https://www.mediawiki.org/xml/export-0.10.xsd), previous versions of the schema don't contain them. Therefore the corresponding fields can be `None`.
+///
+/// The text fields are `String` or `Cow<str>` the same way as for `Page`.
+#[derive(Debug, Default)]
+pub struct Revision<S = String> {
+    /// The edit summary of the revision if any.
+    ///
+    /// Parsed from the text content of the `comment` element. `None` if the element is not present or the edit summary has been hidden.
+    pub comment: Option<S>,
+
+    /// Whether the edit summary has been hidden.
+    ///
+    /// Parsed from the `deleted` attribute of the `comment` element.
+    pub comment_deleted: bool,
+
+    /// The user who made the revision if specified.
+    ///
+    /// Parsed from the `contributor` element. `None` if the element is not present.
+    pub contributor: Option<Contributor<S>>,
+
+    /// The format of the revision if any.
+    ///
+    /// Parsed from the text content of the `format` element. `None` if the element is not present.
+    ///
+    /// For ordinary articles the format is `text/x-wiki`.
+    pub format: Option<S>,
+
+    /// The identifier of the revision if any.
+    ///
+    /// Parsed from the text content of the `id` element. `None` if the element is not present.
+    pub id: Option<u64>,
+
+    /// Whether the revision is marked as a minor edit.
+    ///
+    /// Parsed from the presence of the `minor` element.
+    pub minor: bool,
+
+    /// The model of the revision if any.
+    ///
+    /// Parsed from the text content of the `model` element. `None` if the element is not present.
+    ///
+    /// For ordinary articles the model is `wikitext`.
+    pub model: Option<S>,
+
+    /// The identifier of the revision this revision was created from if any.
+    ///
+    /// Parsed from the text content of the `origin` element. `None` if the element is not present.
+    pub origin: Option<u64>,
+
+    /// The identifier of the previous revision of the page if any.
+    ///
+    /// Parsed from the text content of the `parentid` element. `None` if the element is not present, which is the case for the first revision of a page.
+    pub parent_id: Option<u64>,
+
+    /// The SHA-1 hash of the text in base 36 if any.
+    ///
+    /// Parsed from the text content of the `sha1` element. `None` if the element is not present. For revisions with additional slots, this is the combined hash of the content of all slots.
+    pub sha1: Option<S>,
+
+    /// The additional slots of the revision.
+    ///
+    /// Parsed from the `content` elements, which export schema 0.11 introduced for [Multi-Content Revisions](https://www.mediawiki.org/wiki/Multi-Content_Revisions). The main slot is not included, its content being held by the other fields of the revision. Empty if the revision has no additional slots.
+    pub slots: Vec<Slot<S>>,
+
+    /// The text of the revision.
+    ///
+    /// Parsed from the text content of the `text` element. Empty if the text has been hidden.
+    pub text: S,
+
+    /// The size of the text in bytes if specified.
+    ///
+    /// Parsed from the `bytes` attribute of the `text` element. `None` if the attribute is not present.
+    pub text_bytes: Option<u64>,
+
+    /// Whether the text has been hidden.
+    ///
+    /// Parsed from the `deleted` attribute of the `text` element. This distinguishes a hidden text from an empty one.
+    pub text_deleted: bool,
+
+    /// The time the revision was made if specified.
+    ///
+    /// Parsed from the text content of the `timestamp` element. `None` if the element is not present.
+    pub timestamp: Option<Timestamp>,
 }
 
-impl<R: BufRead> Iterator for Parser<R> {
-    type Item = Result<Page, Error>;
+/// Information about the wiki the dump was exported from.
+///
+/// Parsed from the `siteinfo` element. Every child element is optional in the [schema](https://www.mediawiki.org/xml/export-0.10.xsd), therefore the corresponding fields can be `None`.
+#[derive(Debug)]
+pub struct SiteInfo {
+    /// The URL of the main page of the wiki if any.
+    ///
+    /// Parsed from the text content of the `base` element.
+    pub base: Option<String>,
 
-    fn next(&mut self) -> Option<Self::Item> {
-        Some(match next(self) {
-            Err(error) => Err(error),
-            Ok(item) => Ok(item?),
-        })
-    }
+    /// The case sensitivity of titles if specified.
+    ///
+    /// Parsed from the text content of the `case` element. `None` if the element is not present or its value is not recognized.
+    pub case: Option<Case>,
+
+    /// The database name of the wiki if any.
+    ///
+    /// Parsed from the text content of the `dbname` element. For Wikimedia projects this is for example `enwiki` or `frwiktionary`.
+    pub dbname: Option<String>,
+
+    /// The version of the software that exported the dump if any.
+    ///
+    /// Parsed from the text content of the `generator` element.
+    pub generator: Option<String>,
+
+    /// The namespaces defined by the wiki.
+    ///
+    /// Parsed from the `namespaces` element. Empty if the element is not present.
+    pub namespaces: Namespaces,
+
+    /// The name of the wiki if any.
+    ///
+    /// Parsed from the text content of the `sitename` element.
+    pub sitename: Option<String>,
 }
 
-fn match_namespace(namespace: ResolveResult<'_>) -> bool {
-    matches!(
-        namespace,
-        ResolveResult::Bound(quick_xml::name::Namespace(
-            b"http://www.mediawiki.org/xml/export-0.10/"
-                | b"http://www.mediawiki.org/xml/export-0.11/",
-        ))
-    )
+/// Additional slot of a revision.
+///
+/// Parsed from the `content` element in the `revision` element.
+#[derive(Debug, Default)]
+pub struct Slot<S = String> {
+    /// The format of the content if any.
+    ///
+    /// Parsed from the text content of the `format` element. `None` if the element is not present.
+    pub format: Option<S>,
+
+    /// The model of the content if any.
+    ///
+    /// Parsed from the text content of the `model` element. `None` if the element is not present.
+    ///
+    /// For structured data on Wikimedia Commons the model is `wikibase-mediainfo`.
+    pub model: Option<S>,
+
+    /// The identifier of the revision the content was created in if any.
+    ///
+    /// Parsed from the text content of the `origin` element. `None` if the element is not present.
+    pub origin: Option<u64>,
+
+    /// The role of the slot.
+    ///
+    /// Parsed from the text content of the `role` element. For structured data on Wikimedia Commons the role is `mediainfo`.
+    pub role: S,
+
+    /// The text of the content.
+    ///
+    /// Parsed from the text content of the `text` element. Empty if the text has been hidden.
+    pub text: S,
+
+    /// The size of the text in bytes if specified.
+    ///
+    /// Parsed from the `bytes` attribute of the `text` element. `None` if the attribute is not present.
+    pub text_bytes: Option<u64>,
+
+    /// Whether the text has been hidden.
+    ///
+    /// Parsed from the `deleted` attribute of the `text` element.
+    pub text_deleted: bool,
 }
 
-fn next(parser: &mut Parser<impl BufRead>) -> Result<Option<Page>, Error> {
-    if !parser.started {
-        loop {
-            parser.buffer.clear();
-            if let (namespace, Event::Start(event)) =
-                parser.reader.read_resolved_event_into(&mut parser.buffer)?
-            {
-                if match_namespace(namespace) && event.local_name().as_ref() == b"mediawiki" {
-                    break;
-                }
-                return Err(Error::Format(
-                    parser.reader.buffer_position().try_into().unwrap(),
-                ));
-            }
+/// Text of a revision being read as a stream.
+///
+/// Created by `PageHistory::next_streamed`. The text is read through `std::io::Read`, with entities already unescaped, and is not validated as UTF-8. Only the text of the main slot is streamed, the text of additional slots is parsed as usual.
+pub struct TextStream<'a, R> {
+    entity: Vec<u8>,
+    hasher: sha1_smol::Sha1,
+    parser: &'a mut HistoryParser<R>,
+    pending: Vec<u8>,
+    revision: Revision<Range<usize>>,
+}
+
+impl Case {
+    /// The value as written in dumps, such as `first-letter`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Case::CaseInsensitive => "case-insensitive",
+            Case::CaseSensitive => "case-sensitive",
+            Case::FirstLetter => "first-letter",
         }
-        parser.started = true;
     }
-    loop {
-        parser.buffer.clear();
-        if !match parser
-            .reader
-            //.read_namespaced_event(&mut parser.buffer, &mut parser.namespace_buffer)?
-            .read_resolved_event_into(&mut parser.buffer)?
-        {
-            (_, Event::End(_)) => return Ok(None),
-            (namespace, Event::Start(event)) => {
-                match_namespace(namespace) && event.local_name().as_ref() == b"page"
-            }
-            _ => continue,
-        } {
-            skip_element(parser)?;
-            continue;
+}
+
+impl SchemaVersion {
+    /// The version number, such as `0.10`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaVersion::V0_1 => "0.1",
+            SchemaVersion::V0_2 => "0.2",
+            SchemaVersion::V0_3 => "0.3",
+            SchemaVersion::V0_4 => "0.4",
+            SchemaVersion::V0_5 => "0.5",
+            SchemaVersion::V0_6 => "0.6",
+            SchemaVersion::V0_7 => "0.7",
+            SchemaVersion::V0_8 => "0.8",
+            SchemaVersion::V0_9 => "0.9",
+            SchemaVersion::V0_10 => "0.10",
+            SchemaVersion::V0_11 => "0.11",
         }
-        let mut format = None;
-        let mut model = None;
-        let mut namespace = None;
-        let mut text = None;
-        let mut title = None;
-        loop {
-            parser.buffer.clear();
-            match match parser.reader.read_resolved_event_into(&mut parser.buffer)? {
-                (_, Event::End(_)) => {
-                    return match (namespace, text, title) {
-                        (Some(namespace), Some(text), Some(title)) => Ok(Some(Page {
-                            format,
-                            model,
-                            namespace,
-                            text,
-                            title,
-                        })),
-                        _ => Err(Error::Format(
-                            parser.reader.buffer_position().try_into().unwrap(),
-                        )),
-                    };
-                }
-                (namespace, Event::Start(event)) => {
-                    if match_namespace(namespace) {
-                        match event.local_name().as_ref() {
-                            b"ns" => PageChildElement::Ns,
-                            b"revision" => PageChildElement::Revision,
-                            b"title" => PageChildElement::Title,
-                            _ => PageChildElement::Unknown,
-                        }
-                    } else {
-                        PageChildElement::Unknown
-                    }
-                }
-                _ => continue,
-            } {
-                PageChildElement::Ns => match parse_text(parser, &namespace)?.parse::<i32>() {
-                    Err(_) => {
-                        return Err(Error::Format(
-                            parser.reader.buffer_position().try_into().unwrap(),
-                        ));
-                    }
-                    Ok(value) => {
-                        namespace = Some(Namespace::from(value));
-                        continue;
-                    }
+    }
+}
+
+impl std::fmt::Display for SchemaVersion {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Escape(error, location) => {
+                write!(formatter, "Invalid entity at {}: {}", location, error)
+            }
+            Error::Format(location) => write!(formatter, "Invalid format at {}", location),
+            Error::LimitExceeded(limit, location) => write!(
+                formatter,
+                "The {} exceeds the limit at {}",
+                match limit {
+                    Limit::Revisions => "number of revisions",
+                    Limit::Tag => "length of the tag",
+                    Limit::Text => "length of the text",
+                    Limit::Title => "length of the title",
                 },
-                PageChildElement::Revision => {
-                    if text.is_some() {
-                        return Err(Error::NotSupported(
-                            parser.reader.buffer_position().try_into().unwrap(),
-                        ));
-                    }
-                    loop {
-                        parser.buffer.clear();
-                        match match parser.reader.read_resolved_event_into(&mut parser.buffer)? {
-                            (_, Event::End(_)) => match text {
-                                None => {
-                                    return Err(Error::Format(
-                                        parser.reader.buffer_position().try_into().unwrap(),
-                                    ));
-                                }
-                                Some(_) => break,
-                            },
-                            (namespace, Event::Start(event)) => {
-                                if match_namespace(namespace) {
-                                    match event.local_name().as_ref() {
-                                        b"format" => RevisionChildElement::Format,
-                                        b"model" => RevisionChildElement::Model,
-                                        b"text" => RevisionChildElement::Text,
-                                        _ => RevisionChildElement::Unknown,
-                                    }
-                                } else {
-                                    RevisionChildElement::Unknown
-                                }
-                            }
-                            _ => continue,
-                        } {
-                            RevisionChildElement::Format => {
-                                format = Some(parse_text(parser, &format)?)
-                            }
-                            RevisionChildElement::Model => {
-                                model = Some(parse_text(parser, &model)?)
-                            }
-                            RevisionChildElement::Text => text = Some(parse_text(parser, &text)?),
-                            RevisionChildElement::Unknown => skip_element(parser)?,
-                        }
+                location
+            ),
+            Error::NotSupported(location) => {
+                write!(formatter, "The element at {} is not supported", location)
+            }
+            Error::Sha1Mismatch(None, location) => write!(
+                formatter,
+                "The text of the revision doesn't match its SHA-1 hash at {}",
+                location
+            ),
+            Error::Sha1Mismatch(Some(id), location) => write!(
+                formatter,
+                "The text of the revision with identifier {} doesn't match its SHA-1 hash at {}",
+                id, location
+            ),
+            Error::Skipped(error, range) => write!(
+                formatter,
+                "{}, skipped from offset {} to {}",
+                error, range.start, range.end
+            ),
+            Error::Timestamp(location) => {
+                write!(formatter, "Invalid timestamp at {}", location)
+            }
+            Error::XmlReader(error, location) if *location == Location::default() => {
+                error.fmt(formatter)
+            }
+            Error::XmlReader(error, location) => write!(formatter, "{} at {}", error, location),
+        }
+    }
+}
+
+impl Error {
+    /// The location in the stream where the error occurred.
+    ///
+    /// For `Error::Skipped`, the location of the error that caused the part to be skipped. `None` for errors reading a stream before it is parsed.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::XmlReader(_, location) if *location == Location::default() => None,
+            Error::Escape(_, location)
+            | Error::Format(location)
+            | Error::LimitExceeded(_, location)
+            | Error::NotSupported(location)
+            | Error::Sha1Mismatch(_, location)
+            | Error::Timestamp(location)
+            | Error::XmlReader(_, location) => Some(location),
+            Error::Skipped(error, _) => error.location(),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Escape(error, _) => Some(error.as_ref()),
+            Error::Skipped(error, _) => Some(error.as_ref()),
+            Error::XmlReader(error, _) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(value: quick_xml::Error) -> Self {
+        Error::XmlReader(Box::new(value), Location::default())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::XmlReader(Box::new(value.into()), Location::default())
+    }
+}
+
+impl<R: BufRead> Input for R {
+    fn consume(&mut self, amount: usize) {
+        BufRead::consume(self, amount);
+    }
+
+    fn poll_fill_buf(&mut self, _: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        Poll::Ready(self.fill_buf())
+    }
+
+    fn read_event<'a, 'b>(
+        reader: &'a mut NsReader<Source<Self>>,
+        buffer: &'b mut Vec<u8>,
+    ) -> impl Future<Output = Result<(ResolveResult<'a>, Event<'b>), quick_xml::Error>> {
+        std::future::ready(reader.read_resolved_event_into(buffer))
+    }
+}
+
+impl<R: Input> Source<R> {
+    fn buffered(&mut self) -> &[u8] {
+        // Bytes returned by a stream stay available without waiting until they're consumed.
+        match self.poll_fill_buf(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(Ok(available)) => available,
+            _ => &[],
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        // Bytes inserted before the reader when recovering from an error are not part of the stream.
+        if !self.prefix.is_empty() {
+            self.prefix.drain(..amount.min(self.prefix.len()));
+            return;
+        }
+        let Some(reader) = &mut self.reader else {
+            return;
+        };
+        // The consumed bytes are still available from the reader, which only discards them when consuming.
+        if let Poll::Ready(Ok(available)) =
+            reader.poll_fill_buf(&mut Context::from_waker(Waker::noop()))
+        {
+            let consumed = &available[..amount.min(available.len())];
+            if let Some(last) = consumed.iter().rposition(|byte| *byte == b'\n') {
+                self.line += consumed.iter().filter(|byte| **byte == b'\n').count() as u64;
+                self.line_start = self.offset + last as u64 + 1;
+            }
+        }
+        self.offset += amount as u64;
+        reader.consume(amount);
+    }
+
+    fn poll_fill_buf(&mut self, context: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        if !self.prefix.is_empty() {
+            return Poll::Ready(Ok(&self.prefix));
+        }
+        let Some(reader) = &mut self.reader else {
+            return Poll::Ready(Ok(&[]));
+        };
+        let available = std::task::ready!(reader.poll_fill_buf(context))?;
+        Poll::Ready(match self.tag_limit {
+            None => Ok(available),
+            Some(limit) if self.offset >= limit => {
+                Err(std::io::Error::other("the tag exceeds the limit"))
+            }
+            Some(limit) => Ok(&available[..available.len().min((limit - self.offset) as usize)]),
+        })
+    }
+}
+
+impl<R: BufRead> std::io::Read for Source<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.consume(length);
+        Ok(length)
+    }
+}
+
+impl<R: BufRead> BufRead for Source<R> {
+    fn consume(&mut self, amount: usize) {
+        Source::consume(self, amount);
+    }
+
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        match self.poll_fill_buf(&mut Context::from_waker(Waker::noop())) {
+            Poll::Pending => unreachable!("reading a synchronous stream never waits"),
+            Poll::Ready(result) => result,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "line {}, column {} (offset {})",
+            self.line, self.column, self.offset
+        )?;
+        if !self.path.is_empty() {
+            write!(formatter, " in {}", self.path)?;
+        }
+        match (&self.page_title, self.page_id) {
+            (Some(title), _) => write!(formatter, " of the page {:?}", title),
+            (None, Some(id)) => write!(formatter, " of the page with identifier {}", id),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl NamespaceInfo {
+    /// The namespace as identified by its numeric identifier.
+    pub fn namespace(&self) -> Namespace {
+        Namespace::from(self.key)
+    }
+}
+
+impl Namespaces {
+    /// Finds a namespace by its numeric identifier.
+    pub fn get(&self, key: i32) -> Option<&NamespaceInfo> {
+        self.namespaces
+            .iter()
+            .find(|namespace| namespace.key == key)
+    }
+
+    /// Iterates over the namespaces in the order they appear in the dump.
+    pub fn iter(&self) -> std::slice::Iter<'_, NamespaceInfo> {
+        self.namespaces.iter()
+    }
+
+    /// Finds the namespace of a title from its prefix.
+    ///
+    /// The prefix is the part of the title before the first colon, and is compared to the name and aliases of each namespace the same way MediaWiki does, ignoring case and treating underscores as spaces. Returns the namespace and the rest of the title, or `None` if the title has no prefix matching a namespace other than the main namespace.
+    pub fn lookup<'a>(&self, title: &'a str) -> Option<(&NamespaceInfo, &'a str)> {
+        let (prefix, rest) = title.split_once(':')?;
+        let prefix = prefix.trim_matches([' ', '_']);
+        if prefix.is_empty() {
+            return None;
+        }
+        let namespace = self.namespaces.iter().find(|namespace| {
+            match_name(&namespace.name, prefix)
+                || namespace
+                    .aliases
+                    .iter()
+                    .any(|alias| match_name(alias, prefix))
+        })?;
+        Some((namespace, rest.trim_start_matches([' ', '_'])))
+    }
+}
+
+impl<'a> IntoIterator for &'a Namespaces {
+    type Item = &'a NamespaceInfo;
+    type IntoIter = std::slice::Iter<'a, NamespaceInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<S> Contributor<S> {
+    fn try_assign<T>(
+        &mut self,
+        source: Contributor<T>,
+        f: &mut impl FnMut(&mut S, T) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        S: Default,
+    {
+        self.deleted = source.deleted;
+        self.id = source.id;
+        assign_option(&mut self.ip, source.ip, f)?;
+        assign_option(&mut self.username, source.username, f)
+    }
+
+    fn try_map<T>(
+        self,
+        f: &mut impl FnMut(S) -> Result<T, Error>,
+    ) -> Result<Contributor<T>, Error> {
+        Ok(Contributor {
+            deleted: self.deleted,
+            id: self.id,
+            ip: self.ip.map(&mut *f).transpose()?,
+            username: self.username.map(&mut *f).transpose()?,
+        })
+    }
+}
+
+impl<S> Page<S> {
+    fn try_assign<T>(
+        &mut self,
+        source: Page<T>,
+        f: &mut impl FnMut(&mut S, T) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        S: Default,
+    {
+        self.id = source.id;
+        self.namespace = source.namespace;
+        assign_option(&mut self.redirect, source.redirect, f)?;
+        assign_option(&mut self.restrictions, source.restrictions, f)?;
+        self.revision.try_assign(source.revision, f)?;
+        f(&mut self.title, source.title)
+    }
+
+    fn try_map<T>(self, f: &mut impl FnMut(S) -> Result<T, Error>) -> Result<Page<T>, Error> {
+        Ok(Page {
+            id: self.id,
+            namespace: self.namespace,
+            redirect: self.redirect.map(&mut *f).transpose()?,
+            restrictions: self.restrictions.map(&mut *f).transpose()?,
+            revision: self.revision.try_map(f)?,
+            title: f(self.title)?,
+        })
+    }
+}
+
+impl<S> PageHeader<S> {
+    fn try_map<T>(self, f: &mut impl FnMut(S) -> Result<T, Error>) -> Result<PageHeader<T>, Error> {
+        Ok(PageHeader {
+            id: self.id,
+            namespace: self.namespace,
+            redirect: self.redirect.map(&mut *f).transpose()?,
+            restrictions: self.restrictions.map(&mut *f).transpose()?,
+            title: f(self.title)?,
+        })
+    }
+}
+
+impl<S> Revision<S> {
+    fn try_assign<T>(
+        &mut self,
+        source: Revision<T>,
+        f: &mut impl FnMut(&mut S, T) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        S: Default,
+    {
+        assign_option(&mut self.comment, source.comment, f)?;
+        self.comment_deleted = source.comment_deleted;
+        match source.contributor {
+            None => self.contributor = None,
+            Some(contributor) => self
+                .contributor
+                .get_or_insert_with(Contributor::default)
+                .try_assign(contributor, f)?,
+        }
+        assign_option(&mut self.format, source.format, f)?;
+        self.id = source.id;
+        self.minor = source.minor;
+        assign_option(&mut self.model, source.model, f)?;
+        self.origin = source.origin;
+        self.parent_id = source.parent_id;
+        assign_option(&mut self.sha1, source.sha1, f)?;
+        self.slots.resize_with(source.slots.len(), Slot::default);
+        for (slot, source) in self.slots.iter_mut().zip(source.slots) {
+            slot.try_assign(source, f)?;
+        }
+        f(&mut self.text, source.text)?;
+        self.text_bytes = source.text_bytes;
+        self.text_deleted = source.text_deleted;
+        self.timestamp = source.timestamp;
+        Ok(())
+    }
+
+    fn try_map<T>(self, f: &mut impl FnMut(S) -> Result<T, Error>) -> Result<Revision<T>, Error> {
+        Ok(Revision {
+            comment: self.comment.map(&mut *f).transpose()?,
+            comment_deleted: self.comment_deleted,
+            contributor: match self.contributor {
+                None => None,
+                Some(contributor) => Some(contributor.try_map(f)?),
+            },
+            format: self.format.map(&mut *f).transpose()?,
+            id: self.id,
+            minor: self.minor,
+            model: self.model.map(&mut *f).transpose()?,
+            origin: self.origin,
+            parent_id: self.parent_id,
+            sha1: self.sha1.map(&mut *f).transpose()?,
+            slots: self
+                .slots
+                .into_iter()
+                .map(|slot| slot.try_map(f))
+                .collect::<Result<_, _>>()?,
+            text: f(self.text)?,
+            text_bytes: self.text_bytes,
+            text_deleted: self.text_deleted,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+impl<S> Slot<S> {
+    fn try_assign<T>(
+        &mut self,
+        source: Slot<T>,
+        f: &mut impl FnMut(&mut S, T) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        S: Default,
+    {
+        assign_option(&mut self.format, source.format, f)?;
+        assign_option(&mut self.model, source.model, f)?;
+        self.origin = source.origin;
+        f(&mut self.role, source.role)?;
+        f(&mut self.text, source.text)?;
+        self.text_bytes = source.text_bytes;
+        self.text_deleted = source.text_deleted;
+        Ok(())
+    }
+
+    fn try_map<T>(self, f: &mut impl FnMut(S) -> Result<T, Error>) -> Result<Slot<T>, Error> {
+        Ok(Slot {
+            format: self.format.map(&mut *f).transpose()?,
+            model: self.model.map(&mut *f).transpose()?,
+            origin: self.origin,
+            role: f(self.role)?,
+            text: f(self.text)?,
+            text_bytes: self.text_bytes,
+            text_deleted: self.text_deleted,
+        })
+    }
+}
+
+impl ChildElement for ContentChildElement {
+    const UNKNOWN: Self = ContentChildElement::Unknown;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error> {
+        Ok(match event.local_name().as_ref() {
+            b"format" => ContentChildElement::Format,
+            b"model" => ContentChildElement::Model,
+            b"origin" => ContentChildElement::Origin,
+            b"role" => ContentChildElement::Role,
+            b"text" => ContentChildElement::Text(
+                parse_attribute_range(event, b"bytes", text_buffer)?,
+                parse_deleted(event)?,
+            ),
+            _ => ContentChildElement::Unknown,
+        })
+    }
+}
+
+impl ChildElement for ContributorChildElement {
+    const UNKNOWN: Self = ContributorChildElement::Unknown;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        _text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error> {
+        Ok(match event.local_name().as_ref() {
+            b"id" => ContributorChildElement::Id,
+            b"ip" => ContributorChildElement::Ip,
+            b"username" => ContributorChildElement::Username,
+            _ => ContributorChildElement::Unknown,
+        })
+    }
+}
+
+impl ChildElement for PageChildElement {
+    const UNKNOWN: Self = PageChildElement::Unknown;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error> {
+        Ok(match event.local_name().as_ref() {
+            b"id" => PageChildElement::Id,
+            b"ns" => PageChildElement::Ns,
+            b"redirect" => PageChildElement::Redirect(
+                parse_attribute_range(event, b"title", text_buffer)?
+                    .unwrap_or(text_buffer.len()..text_buffer.len()),
+            ),
+            b"restrictions" => PageChildElement::Restrictions,
+            b"revision" => PageChildElement::Revision,
+            b"title" => PageChildElement::Title,
+            _ => PageChildElement::Unknown,
+        })
+    }
+}
+
+impl ChildElement for RevisionChildElement {
+    const UNKNOWN: Self = RevisionChildElement::Unknown;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error> {
+        Ok(match event.local_name().as_ref() {
+            b"comment" => RevisionChildElement::Comment(parse_deleted(event)?),
+            b"content" => RevisionChildElement::Content,
+            b"contributor" => RevisionChildElement::Contributor(parse_deleted(event)?),
+            b"format" => RevisionChildElement::Format,
+            b"id" => RevisionChildElement::Id,
+            b"minor" => RevisionChildElement::Minor,
+            b"model" => RevisionChildElement::Model,
+            b"origin" => RevisionChildElement::Origin,
+            b"parentid" => RevisionChildElement::ParentId,
+            b"sha1" => RevisionChildElement::Sha1,
+            b"text" => RevisionChildElement::Text(
+                parse_attribute_range(event, b"bytes", text_buffer)?,
+                parse_deleted(event)?,
+            ),
+            b"timestamp" => RevisionChildElement::Timestamp,
+            _ => RevisionChildElement::Unknown,
+        })
+    }
+}
+
+impl ChildElement for SiteInfoChildElement {
+    const UNKNOWN: Self = SiteInfoChildElement::Unknown;
+
+    fn from_start(
+        event: &BytesStart<'_>,
+        _text_buffer: &mut Vec<u8>,
+    ) -> Result<Self, quick_xml::Error> {
+        Ok(match event.local_name().as_ref() {
+            b"base" => SiteInfoChildElement::Base,
+            b"case" => SiteInfoChildElement::Case,
+            b"dbname" => SiteInfoChildElement::DbName,
+            b"generator" => SiteInfoChildElement::Generator,
+            b"namespaces" => SiteInfoChildElement::Namespaces,
+            b"sitename" => SiteInfoChildElement::SiteName,
+            _ => SiteInfoChildElement::Unknown,
+        })
+    }
+}
+
+impl<R: BufRead> HistoryParser<R> {
+    /// Parses the beginning of the next page.
+    ///
+    /// Returns `None` when there are no more pages. Any revisions of the previous page that have not been consumed are skipped first.
+    pub fn next_page(&mut self) -> Option<Result<PageHistory<'_, R>, Error>> {
+        Some(match complete(next_page_history(self)) {
+            Err(error) => Err(complete(recover_history(self, error))),
+            Ok(header) => {
+                let PageHeader {
+                    id,
+                    namespace,
+                    redirect,
+                    restrictions,
+                    title,
+                } = header?;
+                Ok(PageHistory {
+                    id,
+                    namespace,
+                    redirect,
+                    restrictions,
+                    title,
+                    parser: self,
+                })
+            }
+        })
+    }
+
+    /// The version of the export schema the dump conforms to.
+    ///
+    /// See `Parser::schema_version`.
+    pub fn schema_version(&mut self) -> Result<SchemaVersion, Error> {
+        self.parser.schema_version()
+    }
+
+    /// Information about the wiki the dump was exported from.
+    ///
+    /// See `Parser::site_info`.
+    pub fn site_info(&mut self) -> Result<Option<&SiteInfo>, Error> {
+        self.parser.site_info()
+    }
+}
+
+impl<R: BufRead> PageHistory<'_, R> {
+    /// Parses the next revision, borrowing its text from the parser.
+    ///
+    /// Works like `next`, except that text containing no entities is borrowed instead of copied. The revision is valid until the next call.
+    pub fn next_borrowed(&mut self) -> Option<Result<Revision<Cow<'_, str>>, Error>> {
+        Some(match complete(next_revision(self.parser)) {
+            Err(error) => Err(complete(recover_history(self.parser, error))),
+            Ok(revision) => decode_revision(&self.parser.parser, revision?, |text| text),
+        })
+    }
+
+    /// Parses the next revision up to its text, providing the text as a stream.
+    ///
+    /// The text is read from the returned `TextStream` directly from the source, without ever being held in memory as a whole, which is useful for revisions with very large text. The rest of the revision is parsed by `TextStream::finish`. If the stream is dropped without being finished, the rest of the revision is skipped by the next call.
+    pub fn next_streamed(&mut self) -> Option<Result<TextStream<'_, R>, Error>> {
+        Some(match complete(next_streamed(self.parser)) {
+            Err(error) => Err(error),
+            Ok(stream) => Ok(stream?),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for PageHistory<'_, R> {
+    type Item = Result<Revision, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match complete(next_revision(self.parser)) {
+            Err(error) => Err(complete(recover_history(self.parser, error))),
+            Ok(revision) => decode_revision(&self.parser.parser, revision?, Cow::into_owned),
+        })
+    }
+}
+
+impl<R: BufRead> TextStream<'_, R> {
+    /// Skips the rest of the text and parses the rest of the revision.
+    ///
+    /// The text of the returned revision is empty. When verification is enabled with `ParserBuilder::verify_sha1`, the text read from the stream is verified against the hash in the `sha1` element.
+    pub fn finish(mut self) -> Result<Revision, Error> {
+        complete(finish_stream(&mut self)).map_err(|error| locate(&self.parser.parser, error))
+    }
+}
+
+impl<R: BufRead> std::io::Read for TextStream<'_, R> {
+    fn read(&mut self, output: &mut [u8]) -> std::io::Result<usize> {
+        complete(read_stream(self, output)).map_err(|error| {
+            let error = locate(&self.parser.parser, error);
+            std::io::Error::new(
+                match &error {
+                    Error::XmlReader(error, _)
+                        if let quick_xml::Error::Io(error) = error.as_ref() =>
+                    {
+                        error.kind()
                     }
-                    continue;
+                    _ => std::io::ErrorKind::InvalidData,
+                },
+                error,
+            )
+        })
+    }
+}
+
+impl<R: BufRead> Parser<R> {
+    /// Parses the next page, borrowing its text from the parser.
+    ///
+    /// Works like `next`, except that text containing no entities is borrowed instead of copied. The page is valid until the next call, and the memory holding the text is reused for the following pages.
+    pub fn next_borrowed(&mut self) -> Option<Result<Page<Cow<'_, str>>, Error>> {
+        Some(match complete(next(self)) {
+            Err(error) => Err(complete(recover(self, error))),
+            Ok(page) => decode_page(self, page?, |text| text),
+        })
+    }
+
+    /// Parses the next page into an existing page, reusing the memory of its text.
+    ///
+    /// Works like `next`, except that the text of the page is copied into the strings already held by `page` instead of newly allocated ones, so that parsing every page into the same `Page` avoids allocating memory for each page. Returns `false` when there are no more pages. If an error is returned, the content of `page` is unspecified.
+    pub fn parse_into(&mut self, page: &mut Page) -> Result<bool, Error> {
+        let source = match complete(next(self)) {
+            Err(error) => return Err(complete(recover(self, error))),
+            Ok(None) => return Ok(false),
+            Ok(Some(source)) => source,
+        };
+        let sha1 = source.revision.sha1.clone();
+        page.try_assign(source, &mut |target, range| {
+            target.clear();
+            target.push_str(&decode(self, range)?);
+            Ok(())
+        })?;
+        verify_sha1(self, &page.revision, sha1, None)?;
+        Ok(true)
+    }
+
+    /// The version of the export schema the dump conforms to.
+    ///
+    /// Parses the beginning of the dump if no page has been parsed yet.
+    pub fn schema_version(&mut self) -> Result<SchemaVersion, Error> {
+        complete(start(self)).map_err(|error| locate(self, error))
+    }
+
+    /// Information about the wiki the dump was exported from.
+    ///
+    /// Parses the beginning of the dump if no page has been parsed yet, so the information is available before the first page is returned. `None` if the dump doesn't contain a `siteinfo` element.
+    pub fn site_info(&mut self) -> Result<Option<&SiteInfo>, Error> {
+        if let Err(error) = complete(start(self)) {
+            return Err(locate(self, error));
+        }
+        Ok(self.site_info.as_ref())
+    }
+}
+
+impl ParserBuilder {
+    /// Creates a builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a condition pages must satisfy to be returned.
+    ///
+    /// The condition is evaluated on the header of each page, before any revision of the page is parsed. Pages not satisfying the condition are skipped without parsing their revisions, so that their text is neither unescaped nor copied. By default every page is returned.
+    pub fn filter(
+        mut self,
+        filter: impl FnMut(&PageHeader<&str>) -> bool + Send + 'static,
+    ) -> Self {
+        self.options.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Sets whether entities that can't be unescaped are kept as they are.
+    ///
+    /// By default `Error::Escape` is returned for text containing an entity that is not one of the entities predefined by XML or a valid character reference, or an `&` that doesn't start a complete entity, as found in some dumps produced by other tools. When enabled, such entities are kept literally in the text, the other entities in the text being unescaped as usual. Disabled by default.
+    pub fn keep_unknown_entities(mut self, keep: bool) -> Self {
+        self.options.keep_unknown_entities = keep;
+        self
+    }
+
+    /// Sets whether elements are parsed regardless of their namespace.
+    ///
+    /// By default only elements in the namespace of a published version of the export schema are parsed, other elements being ignored, and a dump whose root element is not in such a namespace is rejected. When enabled, elements in any namespace or in no namespace are parsed, so that dumps from other tools that don't declare the namespace can be parsed. A dump whose namespace is not recognized is then treated as conforming to the latest version of the schema.
+    pub fn lenient_namespaces(mut self, lenient: bool) -> Self {
+        self.options.lenient_namespaces = lenient;
+        self
+    }
+
+    /// Sets the maximum for a limit, or removes the limit with `None`.
+    ///
+    /// `Error::LimitExceeded` is returned when the dump exceeds a limit. Lengths are checked while the text or tag is read from the source, so that a text or tag exceeding its limit is never held in memory. A revision exceeding the limit on the number of revisions is skipped. There are no limits by default.
+    pub fn limit(mut self, limit: Limit, maximum: Option<usize>) -> Self {
+        match limit {
+            Limit::Revisions => self.options.max_revisions = maximum,
+            Limit::Tag => self.options.max_tag_length = maximum,
+            Limit::Text => self.options.max_text_length = maximum,
+            Limit::Title => self.options.max_title_length = maximum,
+        }
+        self
+    }
+
+    /// Creates a parser for a stream with the options of the builder.
+    ///
+    /// See `parse`.
+    pub fn parse<R: BufRead>(self, source: R) -> Parser<R> {
+        create_parser(self, source)
+    }
+
+    /// Creates a parser for a stream containing the full history of each page with the options of the builder.
+    ///
+    /// See `parse_history`.
+    pub fn parse_history<R: BufRead>(self, source: R) -> HistoryParser<R> {
+        HistoryParser {
+            in_page: false,
+            parser: self.parse(source),
+            revision_count: 0,
+            revision_pending: false,
+            revision_streamed: false,
+            text_pending: false,
+        }
+    }
+
+    /// Sets whether parsing continues with the next page after an error.
+    ///
+    /// By default parsing ends after an error, the following calls returning `None`, except after `Error::Escape` and `Error::Sha1Mismatch`, which are found once the page or revision has been parsed, and after a revision exceeding the limit on the number of revisions. When enabled, the source is searched from the location of the error for the start tag of the next `page` element, `Error::Skipped` is returned with the error and the part of the source that was skipped, and the following calls continue with the page that was found. A revision exceeding the limit on the number of revisions is skipped as usual. Errors before the beginning of the `mediawiki` element, errors reading the source during the search and errors from `TextStream` are returned as they are. Disabled by default.
+    pub fn recovery(mut self, recovery: bool) -> Self {
+        self.options.recovery = recovery;
+        self
+    }
+
+    /// Sets whether the text of revisions is skipped.
+    ///
+    /// When enabled, the text content of each `text` element is skipped without being unescaped or stored, and the text of every revision and slot is empty. The other fields, including `text_bytes`, are parsed as usual, making it much faster to list the titles or sizes of the pages in a dump. SHA-1 hashes are not verified while the text is skipped. Disabled by default.
+    pub fn skip_text(mut self, skip: bool) -> Self {
+        self.options.skip_text = skip;
+        self
+    }
+
+    /// Sets whether whitespace is removed from the beginning and end of text content.
+    ///
+    /// Applies to the text content of every element, including the text of revisions, except text read with `PageHistory::next_streamed`. Disabled by default.
+    pub fn trim_text(mut self, trim: bool) -> Self {
+        self.options.trim_text = trim;
+        self
+    }
+
+    /// Sets whether the text of each revision is verified against its SHA-1 hash.
+    ///
+    /// When enabled, the text of each revision is hashed and compared to the base 36 encoded hash in the `sha1` element, and `Error::Sha1Mismatch` is returned instead of the page or revision if they differ. Revisions without a `sha1` element or with an empty one, and revisions with hidden text, are not verified. Disabled by default.
+    pub fn verify_sha1(mut self, verify: bool) -> Self {
+        self.options.verify_sha1 = verify;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for Parser<R> {
+    type Item = Result<Page, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match complete(next(self)) {
+            Err(error) => Err(complete(recover(self, error))),
+            Ok(page) => decode_page(self, page?, Cow::into_owned),
+        })
+    }
+}
+
+fn assign_option<S: Default, T>(
+    target: &mut Option<S>,
+    source: Option<T>,
+    f: &mut impl FnMut(&mut S, T) -> Result<(), Error>,
+) -> Result<(), Error> {
+    match source {
+        None => *target = None,
+        Some(source) => f(target.get_or_insert_with(S::default), source)?,
+    }
+    Ok(())
+}
+
+fn base36(digest: sha1_smol::Digest) -> [u8; 31] {
+    let mut digest = digest.bytes();
+    let mut output = [0; 31];
+    for output in output.iter_mut().rev() {
+        let mut remainder = 0;
+        for byte in &mut digest {
+            let value = remainder << 8 | u32::from(*byte);
+            *byte = (value / 36) as u8;
+            remainder = value % 36;
+        }
+        *output = b"0123456789abcdefghijklmnopqrstuvwxyz"[remainder as usize];
+    }
+    output
+}
+
+fn call_filter(
+    parser: &Parser<impl Input>,
+    filter: &mut Filter,
+    header: &RawPageHeader,
+) -> Result<bool, Error> {
+    let decode_option =
+        |range: &Option<Range<usize>>| range.clone().map(|range| decode(parser, range)).transpose();
+    let redirect = decode_option(&header.redirect)?;
+    let restrictions = decode_option(&header.restrictions)?;
+    let title = decode(parser, header.title.clone())?;
+    Ok(filter(&PageHeader {
+        id: header.id,
+        namespace: header.namespace,
+        redirect: redirect.as_deref(),
+        restrictions: restrictions.as_deref(),
+        title: &title,
+    }))
+}
+
+fn clear_text(parser: &mut Parser<impl Input>) {
+    parser.text_buffer.clear();
+    parser.text_locations.clear();
+    parser.text_paths.clear();
+}
+
+fn complete<T>(future: impl Future<Output = T>) -> T {
+    // Futures reading a synchronous stream are always ready, as reading blocks instead of waiting.
+    match std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Pending => unreachable!("reading a synchronous stream never waits"),
+        Poll::Ready(output) => output,
+    }
+}
+
+fn decode(parser: &Parser<impl Input>, range: Range<usize>) -> Result<Cow<'_, str>, Error> {
+    let start = range.start;
+    let text = match std::str::from_utf8(&parser.text_buffer[range]) {
+        Err(error) => {
+            return Err(Error::XmlReader(
+                Box::new(EncodingError::from(error).into()),
+                text_location(parser, start),
+            ));
+        }
+        Ok(text) => text,
+    };
+    match quick_xml::escape::unescape(text) {
+        Err(_) if parser.options.keep_unknown_entities => {
+            Ok(Cow::Owned(unescape_known_entities(text)))
+        }
+        Err(error) => Err(Error::Escape(Box::new(error), text_location(parser, start))),
+        Ok(text) => Ok(text),
+    }
+}
+
+fn decode_page<'a, S: AsRef<str>>(
+    parser: &'a Parser<impl Input>,
+    page: Page<Range<usize>>,
+    convert: impl Fn(Cow<'a, str>) -> S,
+) -> Result<Page<S>, Error> {
+    let sha1 = page.revision.sha1.clone();
+    let page = page.try_map(&mut |range| Ok(convert(decode(parser, range)?)))?;
+    verify_sha1(parser, &page.revision, sha1, None)?;
+    Ok(page)
+}
+
+fn decode_revision<'a, S: AsRef<str>>(
+    parser: &'a Parser<impl Input>,
+    revision: Revision<Range<usize>>,
+    convert: impl Fn(Cow<'a, str>) -> S,
+) -> Result<Revision<S>, Error> {
+    let sha1 = revision.sha1.clone();
+    let revision = revision.try_map(&mut |range| Ok(convert(decode(parser, range)?)))?;
+    verify_sha1(parser, &revision, sha1, None)?;
+    Ok(revision)
+}
+
+fn eof_error(parser: &mut Parser<impl Input>) -> Error {
+    // The source ended before the end of the dump, so there is nothing left to parse.
+    parser.finished = true;
+    format_error(parser)
+}
+
+fn filter_page(parser: &mut Parser<impl Input>, header: &RawPageHeader) -> Result<bool, Error> {
+    // The filter is taken from the parser while it's called, as the header it's given borrows the text from the parser.
+    let Some(mut filter) = parser.options.filter.take() else {
+        return Ok(true);
+    };
+    let result = call_filter(parser, &mut filter, header);
+    parser.options.filter = Some(filter);
+    result
+}
+
+fn consume_stream(parser: &mut Parser<impl Input>, length: usize) {
+    parser.reader.get_mut().consume(length);
+}
+
+async fn fill_stream(source: &mut Source<impl Input>) -> Result<&[u8], Error> {
+    // Text read directly from the source follows the tag read last, so it isn't subject to the limit on the length of tags.
+    source.tag_limit = None;
+    std::future::poll_fn(|context| source.poll_fill_buf(context).map_ok(|_| ())).await?;
+    Ok(source.buffered())
+}
+
+fn create_parser<R>(builder: ParserBuilder, source: R) -> Parser<R> {
+    let reader = create_reader(Source {
+        line: 1,
+        line_start: 0,
+        offset: 0,
+        prefix: vec![],
+        reader: Some(source),
+        tag_limit: None,
+    });
+    Parser {
+        buffer: vec![],
+        finished: false,
+        options: builder.options,
+        page_id: None,
+        page_pending: false,
+        page_title: vec![],
+        path: String::new(),
+        reader,
+        root: vec![],
+        schema_version: None,
+        site_info: None,
+        text_buffer: vec![],
+        text_locations: vec![],
+        text_paths: String::new(),
+    }
+}
+
+fn create_reader<R>(source: Source<R>) -> NsReader<Source<R>> {
+    let mut reader = NsReader::from_reader(source);
+    reader.config_mut().expand_empty_elements = true;
+    reader
+}
+
+async fn finish_stream(stream: &mut TextStream<'_, impl Input>) -> Result<Revision, Error> {
+    let mut buffer = [0; 4096];
+    while read_stream(stream, &mut buffer).await? != 0 {}
+    let parser = &mut *stream.parser;
+    parser.revision_streamed = false;
+    skip_element(&mut parser.parser).await?;
+    let mut revision = std::mem::take(&mut stream.revision);
+    let mut text = Some(revision.text.clone());
+    parse_revision_children(&mut parser.parser, &mut revision, &mut text, false).await?;
+    let sha1 = revision.sha1.clone();
+    let revision =
+        revision.try_map(&mut |range| Ok(decode(&parser.parser, range)?.into_owned()))?;
+    verify_sha1(
+        &parser.parser,
+        &revision,
+        sha1,
+        Some(stream.hasher.digest()),
+    )?;
+    Ok(revision)
+}
+
+fn format_error(parser: &Parser<impl Input>) -> Error {
+    Error::Format(location(parser))
+}
+
+fn is_empty_element(parser: &Parser<impl Input>) -> bool {
+    // The start tag of an empty element, expanded by the XML reader, is left in the buffer ending with a slash.
+    parser.buffer.ends_with(b"/")
+}
+
+fn locate(parser: &Parser<impl Input>, error: Error) -> Error {
+    // Errors from the XML reader are converted without a location where they occur. Since parsing stops at an error, the location of the parser when the error is returned is the location of the error.
+    let source = parser.reader.get_ref();
+    match error {
+        // Reading a tag longer than the limit fails with an error reading the source.
+        Error::XmlReader(error, location)
+            if location == Location::default()
+                && matches!(*error, quick_xml::Error::Io(_))
+                && source.tag_limit.is_some_and(|limit| source.offset >= limit) =>
+        {
+            Error::LimitExceeded(Limit::Tag, self::location(parser))
+        }
+        Error::XmlReader(error, location) if location == Location::default() => {
+            Error::XmlReader(error, self::location(parser))
+        }
+        error => error,
+    }
+}
+
+fn location(parser: &Parser<impl Input>) -> Location {
+    let source = parser.reader.get_ref();
+    location_at(
+        parser,
+        source.line,
+        source.line_start,
+        source.offset,
+        &parser.path,
+    )
+}
+
+fn location_at(
+    parser: &Parser<impl Input>,
+    line: u64,
+    line_start: u64,
+    offset: u64,
+    path: &str,
+) -> Location {
+    let in_page = path.split('/').nth(1) == Some("page");
+    Location {
+        column: offset - line_start + 1,
+        line,
+        offset,
+        page_id: parser.page_id.filter(|_| in_page),
+        page_title: (in_page && !parser.page_title.is_empty()).then(|| {
+            let title = String::from_utf8_lossy(&parser.page_title);
+            match quick_xml::escape::unescape(&title) {
+                Err(_) => title.into_owned(),
+                Ok(title) => title.into_owned(),
+            }
+        }),
+        path: path.to_string(),
+    }
+}
+
+fn match_namespace(lenient: bool, namespace: ResolveResult<'_>) -> bool {
+    lenient || match_schema_version(namespace).is_some()
+}
+
+fn match_schema_version(namespace: ResolveResult<'_>) -> Option<SchemaVersion> {
+    match namespace {
+        ResolveResult::Bound(quick_xml::name::Namespace(namespace)) => Some(
+            match namespace
+                .strip_prefix(b"http://www.mediawiki.org/xml/export-")?
+                .strip_suffix(b"/")?
+            {
+                b"0.1" => SchemaVersion::V0_1,
+                b"0.2" => SchemaVersion::V0_2,
+                b"0.3" => SchemaVersion::V0_3,
+                b"0.4" => SchemaVersion::V0_4,
+                b"0.5" => SchemaVersion::V0_5,
+                b"0.6" => SchemaVersion::V0_6,
+                b"0.7" => SchemaVersion::V0_7,
+                b"0.8" => SchemaVersion::V0_8,
+                b"0.9" => SchemaVersion::V0_9,
+                b"0.10" => SchemaVersion::V0_10,
+                b"0.11" => SchemaVersion::V0_11,
+                _ => return None,
+            },
+        ),
+        _ => None,
+    }
+}
+
+fn match_name(name: &str, prefix: &str) -> bool {
+    let normalize = |character: char| if character == '_' { ' ' } else { character };
+    name.chars()
+        .map(normalize)
+        .flat_map(char::to_lowercase)
+        .eq(prefix.chars().map(normalize).flat_map(char::to_lowercase))
+}
+
+async fn next(parser: &mut Parser<impl Input>) -> Result<Option<Page<Range<usize>>>, Error> {
+    clear_text(parser);
+    let header = match next_page_header(parser).await? {
+        None => return Ok(None),
+        Some((_, false)) => return Err(format_error(parser)),
+        Some((header, true)) => header,
+    };
+    let revision = parse_revision(parser).await?;
+    loop {
+        match read_child(parser).await? {
+            None => break,
+            Some(PageChildElement::Revision) => {
+                return Err(Error::NotSupported(location(parser)));
+            }
+            Some(_) => skip_element(parser).await?,
+        }
+    }
+    Ok(Some(Page {
+        id: header.id,
+        namespace: header.namespace,
+        redirect: header.redirect,
+        restrictions: header.restrictions,
+        revision,
+        title: header.title,
+    }))
+}
+
+async fn next_page_header(
+    parser: &mut Parser<impl Input>,
+) -> Result<Option<(RawPageHeader, bool)>, Error> {
+    if parser.finished {
+        return Ok(None);
+    }
+    start(parser).await?;
+    loop {
+        if parser.page_pending {
+            parser.page_pending = false;
+        } else if !next_page_start(parser).await? {
+            return Ok(None);
+        }
+        let start = parser.text_buffer.len();
+        let (header, at_revision) = parse_page_header(parser).await?;
+        if filter_page(parser, &header)? {
+            return Ok(Some((header, at_revision)));
+        }
+        if at_revision {
+            skip_element(parser).await?;
+            skip_element(parser).await?;
+        }
+        parser.text_buffer.truncate(start);
+    }
+}
+
+async fn parse_page_header(
+    parser: &mut Parser<impl Input>,
+) -> Result<(RawPageHeader, bool), Error> {
+    parser.page_id = None;
+    parser.page_title.clear();
+    let mut id = None;
+    let mut namespace = None;
+    let mut redirect = None;
+    let mut restrictions = None;
+    let mut title = None;
+    let at_revision = loop {
+        match read_child(parser).await? {
+            None => break false,
+            Some(PageChildElement::Id) => {
+                id = Some(parse_integer(parser, &id).await?);
+                parser.page_id = id;
+            }
+            Some(PageChildElement::Ns) => {
+                namespace = Some(Namespace::from(
+                    parse_integer::<i32>(parser, &namespace).await?,
+                ))
+            }
+            Some(PageChildElement::Redirect(target)) => {
+                if redirect.is_some() {
+                    return Err(format_error(parser));
                 }
-                PageChildElement::Title => {
-                    title = Some(parse_text(parser, &title)?);
-                    continue;
+                redirect = Some(target);
+                skip_element(parser).await?;
+            }
+            Some(PageChildElement::Restrictions) => {
+                restrictions = Some(parse_text(parser, &restrictions).await?)
+            }
+            Some(PageChildElement::Revision) => break true,
+            Some(PageChildElement::Title) => {
+                let range = read_text(parser, &title, Some(Limit::Title), true).await?;
+                parser.page_title.clear();
+                parser
+                    .page_title
+                    .extend_from_slice(&parser.text_buffer[range.clone()]);
+                title = Some(range);
+            }
+            Some(PageChildElement::Unknown) => skip_element(parser).await?,
+        }
+    };
+    if namespace.is_none()
+        && let Some(title) = &title
+    {
+        let title = decode(parser, title.clone())?;
+        let namespaces = match &parser.site_info {
+            Some(site_info) if site_info.namespaces.iter().next().is_some() => {
+                &site_info.namespaces
+            }
+            _ => &CANONICAL_NAMESPACES,
+        };
+        namespace = Some(match namespaces.lookup(&title) {
+            None => Namespace::Main,
+            Some((namespace, _)) => namespace.namespace(),
+        });
+    }
+    match (namespace, title) {
+        (Some(namespace), Some(title)) => Ok((
+            PageHeader {
+                id,
+                namespace,
+                redirect,
+                restrictions,
+                title,
+            },
+            at_revision,
+        )),
+        _ => Err(format_error(parser)),
+    }
+}
+
+async fn next_page_start(parser: &mut Parser<impl Input>) -> Result<bool, Error> {
+    if parser.finished {
+        return Ok(false);
+    }
+    loop {
+        prepare_event(parser).await?;
+        if match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+            (_, Event::End(_)) => {
+                pop_path(&mut parser.path);
+                parser.finished = true;
+                return Ok(false);
+            }
+            (_, Event::Eof) => return Err(eof_error(parser)),
+            (namespace, Event::Start(event)) => {
+                push_path(&mut parser.path, event.local_name().as_ref());
+                match_namespace(parser.options.lenient_namespaces, namespace)
+                    && event.local_name().as_ref() == b"page"
+            }
+            _ => continue,
+        } {
+            return Ok(true);
+        }
+        skip_element(parser).await?;
+    }
+}
+
+async fn next_page_history(
+    parser: &mut HistoryParser<impl Input>,
+) -> Result<Option<PageHeader<String>>, Error> {
+    skip_streamed(parser).await?;
+    clear_text(&mut parser.parser);
+    parser.revision_count = 0;
+    if parser.in_page {
+        parser.in_page = false;
+        if parser.revision_pending {
+            parser.revision_pending = false;
+            skip_element(&mut parser.parser).await?;
+        }
+        skip_element(&mut parser.parser).await?;
+    }
+    Ok(match next_page_header(&mut parser.parser).await? {
+        None => None,
+        Some((header, at_revision)) => {
+            parser.in_page = at_revision;
+            parser.revision_pending = at_revision;
+            Some(header.try_map(&mut |range| Ok(decode(&parser.parser, range)?.into_owned()))?)
+        }
+    })
+}
+
+async fn next_revision(
+    parser: &mut HistoryParser<impl Input>,
+) -> Result<Option<Revision<Range<usize>>>, Error> {
+    Ok(if next_revision_start(parser).await? {
+        Some(parse_revision(&mut parser.parser).await?)
+    } else {
+        None
+    })
+}
+
+async fn next_revision_start(parser: &mut HistoryParser<impl Input>) -> Result<bool, Error> {
+    skip_streamed(parser).await?;
+    clear_text(&mut parser.parser);
+    if !parser.in_page {
+        return Ok(false);
+    }
+    if parser.revision_pending {
+        parser.revision_pending = false;
+    } else {
+        loop {
+            match read_child(&mut parser.parser).await? {
+                None => {
+                    parser.in_page = false;
+                    return Ok(false);
                 }
-                PageChildElement::Unknown => skip_element(parser)?,
+                Some(PageChildElement::Revision) => break,
+                Some(_) => skip_element(&mut parser.parser).await?,
             }
         }
     }
+    parser.revision_count += 1;
+    if let Some(maximum) = parser.parser.options.max_revisions
+        && parser.revision_count > maximum
+    {
+        skip_element(&mut parser.parser).await?;
+        return Err(Error::LimitExceeded(
+            Limit::Revisions,
+            location(&parser.parser),
+        ));
+    }
+    Ok(true)
+}
+
+async fn next_streamed<R: Input>(
+    parser: &mut HistoryParser<R>,
+) -> Result<Option<TextStream<'_, R>>, Error> {
+    let revision = match next_streamed_revision(parser).await {
+        Err(error) => return Err(recover_history(parser, error).await),
+        Ok(None) => return Ok(None),
+        Ok(Some(revision)) => revision,
+    };
+    parser.revision_streamed = true;
+    parser.text_pending = !is_empty_element(&parser.parser);
+    Ok(Some(TextStream {
+        entity: vec![],
+        hasher: sha1_smol::Sha1::new(),
+        parser,
+        pending: vec![],
+        revision,
+    }))
+}
+
+async fn next_streamed_revision(
+    parser: &mut HistoryParser<impl Input>,
+) -> Result<Option<Revision<Range<usize>>>, Error> {
+    if !next_revision_start(parser).await? {
+        return Ok(None);
+    }
+    let mut revision = Revision::default();
+    let mut text = None;
+    if !parse_revision_children(&mut parser.parser, &mut revision, &mut text, true).await? {
+        return Err(format_error(&parser.parser));
+    }
+    revision.text = text.unwrap_or_default();
+    Ok(Some(revision))
+}
+
+/// Opens a file and creates a parser for it.
+///
+/// The file is decompressed with `decompress` if it's compressed, so that `.xml`, `.xml.bz2`, `.xml.gz`, `.xml.xz` and `.xml.zst` files can be parsed alike, provided that the cargo feature for the format is enabled. For parsing the full history or setting options, pass the result of `decompress` to `parse_history` or `ParserBuilder` instead.
+///
+/// # Errors
+///
+/// Fails if opening the file or detecting its compression fails, or with the error kind `Unsupported` if the cargo feature required to decompress it is not enabled.
+pub fn open(path: impl AsRef<Path>) -> std::io::Result<Parser<Box<dyn BufRead + Send>>> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    Ok(parse(decompress(file)?))
 }
 
 /// Creates a parser for a stream.
 ///
 /// The stream is parsed as an XML dump exported from Mediawiki. The parser is an iterator over the pages in the dump.
 pub fn parse<R: BufRead>(source: R) -> Parser<R> {
-    let mut reader = NsReader::from_reader(source);
-    reader.config_mut().expand_empty_elements = true;
-    Parser {
-        buffer: vec![],
-        reader,
-        started: false,
+    ParserBuilder::new().parse(source)
+}
+
+/// Creates a parser for a stream containing the full history of each page.
+///
+/// The stream is parsed as an XML dump exported from Mediawiki, where each page can contain any number of revisions. Pages are obtained from `HistoryParser::next_page`, and each page is an iterator over its revisions.
+pub fn parse_history<R: BufRead>(source: R) -> HistoryParser<R> {
+    ParserBuilder::new().parse_history(source)
+}
+
+fn parse_attribute(
+    event: &BytesStart<'_>,
+    name: &[u8],
+) -> Result<Option<String>, quick_xml::Error> {
+    Ok(match event.try_get_attribute(name)? {
+        None => None,
+        Some(attribute) => Some(attribute.unescape_value()?.into_owned()),
+    })
+}
+
+fn parse_attribute_range(
+    event: &BytesStart<'_>,
+    name: &[u8],
+    text_buffer: &mut Vec<u8>,
+) -> Result<Option<Range<usize>>, quick_xml::Error> {
+    Ok(match event.try_get_attribute(name)? {
+        None => None,
+        Some(attribute) => {
+            let start = text_buffer.len();
+            text_buffer.extend_from_slice(&attribute.value);
+            Some(start..text_buffer.len())
+        }
+    })
+}
+
+fn parse_bytes(
+    parser: &mut Parser<impl Input>,
+    bytes: Option<Range<usize>>,
+) -> Result<Option<u64>, Error> {
+    let range = match bytes {
+        None => return Ok(None),
+        Some(range) => range,
+    };
+    let start = range.start;
+    let bytes = decode(parser, range)?.parse();
+    parser.text_buffer.truncate(start);
+    match bytes {
+        Err(_) => Err(format_error(parser)),
+        Ok(bytes) => Ok(Some(bytes)),
+    }
+}
+
+fn parse_case(value: &str) -> Option<Case> {
+    match value {
+        "case-insensitive" => Some(Case::CaseInsensitive),
+        "case-sensitive" => Some(Case::CaseSensitive),
+        "first-letter" => Some(Case::FirstLetter),
+        _ => None,
+    }
+}
+
+async fn parse_content_text(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+) -> Result<Range<usize>, Error> {
+    if parser.options.skip_text {
+        read_text(parser, output, None, false).await
+    } else {
+        read_text(parser, output, Some(Limit::Text), true).await
+    }
+}
+
+async fn parse_contributor(
+    parser: &mut Parser<impl Input>,
+    deleted: bool,
+) -> Result<Contributor<Range<usize>>, Error> {
+    let mut id = None;
+    let mut ip = None;
+    let mut username = None;
+    loop {
+        match read_child(parser).await? {
+            None => break,
+            Some(ContributorChildElement::Id) => id = Some(parse_integer(parser, &id).await?),
+            Some(ContributorChildElement::Ip) => ip = Some(parse_text(parser, &ip).await?),
+            Some(ContributorChildElement::Username) => {
+                username = Some(parse_text(parser, &username).await?)
+            }
+            Some(ContributorChildElement::Unknown) => skip_element(parser).await?,
+        }
+    }
+    Ok(Contributor {
+        deleted,
+        id,
+        ip,
+        username,
+    })
+}
+
+fn parse_deleted(event: &BytesStart<'_>) -> Result<bool, quick_xml::Error> {
+    Ok(event.try_get_attribute(b"deleted")?.is_some())
+}
+
+async fn parse_integer<T: std::str::FromStr>(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+) -> Result<T, Error> {
+    parse_value(parser, output, Error::Format).await
+}
+
+async fn parse_namespaces(parser: &mut Parser<impl Input>) -> Result<Namespaces, Error> {
+    let mut namespaces = vec![];
+    loop {
+        prepare_event(parser).await?;
+        let attributes = match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+            (_, Event::End(_)) => {
+                pop_path(&mut parser.path);
+                return Ok(Namespaces { namespaces });
+            }
+            (_, Event::Eof) => return Err(eof_error(parser)),
+            (namespace, Event::Start(event)) => {
+                push_path(&mut parser.path, event.local_name().as_ref());
+                if match_namespace(parser.options.lenient_namespaces, namespace)
+                    && event.local_name().as_ref() == b"namespace"
+                {
+                    Some((
+                        parse_attribute(&event, b"case")?,
+                        parse_attribute(&event, b"key")?,
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ => continue,
+        };
+        let (case, key) = match attributes {
+            None => {
+                skip_element(parser).await?;
+                continue;
+            }
+            Some((case, Some(key))) => (case, key),
+            Some((_, None)) => return Err(format_error(parser)),
+        };
+        let key = match key.parse() {
+            Err(_) => return Err(format_error(parser)),
+            Ok(key) => key,
+        };
+        let name = parse_string(parser, &None::<()>).await?;
+        namespaces.push(NamespaceInfo {
+            aliases: Namespace::from(key)
+                .canonical_names()
+                .iter()
+                .filter(|alias| !match_name(alias, &name))
+                .map(|alias| alias.to_string())
+                .collect(),
+            case: case.as_deref().and_then(parse_case),
+            key,
+            name,
+        });
+    }
+}
+
+async fn parse_revision(parser: &mut Parser<impl Input>) -> Result<Revision<Range<usize>>, Error> {
+    let mut revision = Revision::default();
+    let mut text = None;
+    parse_revision_children(parser, &mut revision, &mut text, false).await?;
+    match text {
+        None => Err(format_error(parser)),
+        Some(text) => {
+            revision.text = text;
+            Ok(revision)
+        }
+    }
+}
+
+async fn parse_revision_children(
+    parser: &mut Parser<impl Input>,
+    revision: &mut Revision<Range<usize>>,
+    text: &mut Option<Range<usize>>,
+    stream_text: bool,
+) -> Result<bool, Error> {
+    loop {
+        match read_child(parser).await? {
+            None => return Ok(false),
+            Some(RevisionChildElement::Comment(deleted)) => {
+                if revision.comment_deleted {
+                    return Err(format_error(parser));
+                }
+                let value = parse_text(parser, &revision.comment).await?;
+                revision.comment_deleted = deleted;
+                if !deleted {
+                    revision.comment = Some(value);
+                }
+            }
+            Some(RevisionChildElement::Content) => revision.slots.push(parse_slot(parser).await?),
+            Some(RevisionChildElement::Contributor(deleted)) => {
+                if revision.contributor.is_some() {
+                    return Err(format_error(parser));
+                }
+                revision.contributor = Some(parse_contributor(parser, deleted).await?);
+            }
+            Some(RevisionChildElement::Format) => {
+                revision.format = Some(parse_text(parser, &revision.format).await?)
+            }
+            Some(RevisionChildElement::Id) => {
+                revision.id = Some(parse_integer(parser, &revision.id).await?)
+            }
+            Some(RevisionChildElement::Minor) => {
+                revision.minor = true;
+                skip_element(parser).await?;
+            }
+            Some(RevisionChildElement::Model) => {
+                revision.model = Some(parse_text(parser, &revision.model).await?)
+            }
+            Some(RevisionChildElement::Origin) => {
+                revision.origin = Some(parse_integer(parser, &revision.origin).await?)
+            }
+            Some(RevisionChildElement::ParentId) => {
+                revision.parent_id = Some(parse_integer(parser, &revision.parent_id).await?)
+            }
+            Some(RevisionChildElement::Sha1) => {
+                revision.sha1 = Some(parse_text(parser, &revision.sha1).await?)
+            }
+            Some(RevisionChildElement::Text(bytes, deleted)) => {
+                revision.text_bytes = parse_bytes(parser, bytes)?;
+                revision.text_deleted = deleted;
+                if stream_text {
+                    if text.is_some() {
+                        return Err(format_error(parser));
+                    }
+                    let end = parser.text_buffer.len();
+                    *text = Some(end..end);
+                    return Ok(true);
+                }
+                *text = Some(parse_content_text(parser, text).await?);
+            }
+            Some(RevisionChildElement::Timestamp) => {
+                revision.timestamp =
+                    Some(parse_value(parser, &revision.timestamp, Error::Timestamp).await?)
+            }
+            Some(RevisionChildElement::Unknown) => skip_element(parser).await?,
+        }
+    }
+}
+
+async fn parse_site_info(parser: &mut Parser<impl Input>) -> Result<SiteInfo, Error> {
+    let mut base = None;
+    let mut case = None;
+    let mut dbname = None;
+    let mut generator = None;
+    let mut namespaces = None;
+    let mut sitename = None;
+    loop {
+        match read_child(parser).await? {
+            None => break,
+            Some(SiteInfoChildElement::Base) => base = Some(parse_string(parser, &base).await?),
+            Some(SiteInfoChildElement::Case) => {
+                let value = parse_string(parser, &case).await?;
+                case = parse_case(&value);
+            }
+            Some(SiteInfoChildElement::DbName) => {
+                dbname = Some(parse_string(parser, &dbname).await?)
+            }
+            Some(SiteInfoChildElement::Generator) => {
+                generator = Some(parse_string(parser, &generator).await?)
+            }
+            Some(SiteInfoChildElement::Namespaces) => {
+                if namespaces.is_some() {
+                    return Err(format_error(parser));
+                }
+                namespaces = Some(parse_namespaces(parser).await?);
+            }
+            Some(SiteInfoChildElement::SiteName) => {
+                sitename = Some(parse_string(parser, &sitename).await?)
+            }
+            Some(SiteInfoChildElement::Unknown) => skip_element(parser).await?,
+        }
+    }
+    Ok(SiteInfo {
+        base,
+        case,
+        dbname,
+        generator,
+        namespaces: namespaces.unwrap_or_default(),
+        sitename,
+    })
+}
+
+async fn parse_slot(parser: &mut Parser<impl Input>) -> Result<Slot<Range<usize>>, Error> {
+    let mut format = None;
+    let mut model = None;
+    let mut origin = None;
+    let mut role = None;
+    let mut text = None;
+    let mut text_bytes = None;
+    let mut text_deleted = false;
+    loop {
+        match read_child(parser).await? {
+            None => break,
+            Some(ContentChildElement::Format) => format = Some(parse_text(parser, &format).await?),
+            Some(ContentChildElement::Model) => model = Some(parse_text(parser, &model).await?),
+            Some(ContentChildElement::Origin) => {
+                origin = Some(parse_integer(parser, &origin).await?)
+            }
+            Some(ContentChildElement::Role) => role = Some(parse_text(parser, &role).await?),
+            Some(ContentChildElement::Text(bytes, deleted)) => {
+                text_bytes = parse_bytes(parser, bytes)?;
+                text_deleted = deleted;
+                text = Some(parse_content_text(parser, &text).await?);
+            }
+            Some(ContentChildElement::Unknown) => skip_element(parser).await?,
+        }
+    }
+    match (role, text) {
+        (Some(role), Some(text)) => Ok(Slot {
+            format,
+            model,
+            origin,
+            role,
+            text,
+            text_bytes,
+            text_deleted,
+        }),
+        _ => Err(format_error(parser)),
     }
 }
 
-fn parse_text(
-    parser: &mut Parser<impl BufRead>,
+async fn parse_string(
+    parser: &mut Parser<impl Input>,
     output: &Option<impl Sized>,
 ) -> Result<String, Error> {
-    if output.is_some() {
-        return Err(Error::Format(
-            parser.reader.buffer_position().try_into().unwrap(),
-        ));
+    parse_text_with(parser, output, |text| text.into_owned()).await
+}
+
+async fn parse_text(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+) -> Result<Range<usize>, Error> {
+    read_text(parser, output, Some(Limit::Text), true).await
+}
+
+async fn parse_text_with<T>(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+    convert: impl FnOnce(Cow<'_, str>) -> T,
+) -> Result<T, Error> {
+    // The text is converted before the end tag is read, so that errors indicate the location in the element.
+    let range = read_text_content(parser, output, Some(Limit::Text), true).await?;
+    let start = range.start;
+    let value = convert(decode(parser, range)?);
+    parser.text_buffer.truncate(start);
+    read_end_tag(parser).await?;
+    Ok(value)
+}
+
+async fn parse_value<T: std::str::FromStr>(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+    error: fn(Location) -> Error,
+) -> Result<T, Error> {
+    let range = read_text_content(parser, output, Some(Limit::Text), true).await?;
+    let start = range.start;
+    let value = decode(parser, range)?.parse();
+    parser.text_buffer.truncate(start);
+    match value {
+        Err(_) => Err(error(location(parser))),
+        Ok(value) => {
+            read_end_tag(parser).await?;
+            Ok(value)
+        }
     }
+}
+
+fn pop_path(path: &mut String) {
+    path.truncate(path.rfind('/').unwrap_or(0));
+}
+
+async fn prepare_event(parser: &mut Parser<impl Input>) -> Result<(), Error> {
+    // Text is skipped directly in the source and the length of tags is limited while the XML reader reads them, so that the XML reader never buffers more than the limits.
+    read_text_content(parser, &None::<()>, None, false).await?;
     parser.buffer.clear();
-    let text = match parser
-        .reader
-        .read_resolved_event_into(&mut parser.buffer)?
+    let source = parser.reader.get_mut();
+    source.tag_limit = parser
+        .options
+        .max_tag_length
+        .map(|maximum| source.offset + maximum as u64);
+    Ok(())
+}
+
+fn push_path(path: &mut String, name: &[u8]) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(&String::from_utf8_lossy(name));
+}
+
+async fn read_end_tag(parser: &mut Parser<impl Input>) -> Result<(), Error> {
+    prepare_event(parser).await?;
+    if let Event::End(_) = Input::read_event(&mut parser.reader, &mut parser.buffer)
+        .await?
         .1
     {
-        Event::Text(text) => text.unescape().unwrap().to_string(),
-        Event::End { .. } => return Ok(String::new()),
-        _ => {
-            return Err(Error::Format(
-                parser.reader.buffer_position().try_into().unwrap(),
-            ));
+        pop_path(&mut parser.path);
+        Ok(())
+    } else {
+        Err(format_error(parser))
+    }
+}
+
+async fn read_text(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+    limit: Option<Limit>,
+    store: bool,
+) -> Result<Range<usize>, Error> {
+    let range = read_text_content(parser, output, limit, store).await?;
+    read_end_tag(parser).await?;
+    Ok(range)
+}
+
+async fn read_text_content(
+    parser: &mut Parser<impl Input>,
+    output: &Option<impl Sized>,
+    limit: Option<Limit>,
+    store: bool,
+) -> Result<Range<usize>, Error> {
+    if output.is_some() {
+        return Err(format_error(parser));
+    }
+    let maximum = match limit {
+        None => None,
+        Some(Limit::Revisions) => parser.options.max_revisions,
+        Some(Limit::Tag) => parser.options.max_tag_length,
+        Some(Limit::Text) => parser.options.max_text_length,
+        Some(Limit::Title) => parser.options.max_title_length,
+    };
+    // The raw text is read directly from the source into the text buffer, to be unescaped only when the page is returned.
+    let start = parser.text_buffer.len();
+    if !is_empty_element(parser) {
+        let mut length = 0;
+        loop {
+            let available = fill_stream(parser.reader.get_mut()).await?;
+            if available.is_empty() {
+                return Err(eof_error(parser));
+            }
+            let end = available.iter().position(|byte| *byte == b'<');
+            let end = end.unwrap_or(available.len());
+            length += end;
+            if let (Some(limit), Some(maximum)) = (limit, maximum)
+                && length > maximum
+            {
+                return Err(Error::LimitExceeded(limit, location(parser)));
+            }
+            if store {
+                parser.text_buffer.extend_from_slice(&available[..end]);
+            }
+            let found = end < available.len();
+            consume_stream(parser, end);
+            if found {
+                break;
+            }
+        }
+    }
+    let mut range = start..parser.text_buffer.len();
+    if parser.options.trim_text {
+        let text = &parser.text_buffer[range.clone()];
+        let leading = text
+            .iter()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        let trailing = text[leading..]
+            .iter()
+            .rev()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        range = start + leading..range.end - trailing;
+        parser.text_buffer.truncate(range.end);
+    }
+    Ok(range)
+}
+
+async fn read_stream(
+    stream: &mut TextStream<'_, impl Input>,
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    // Long enough for any predefined entity or character reference.
+    const MAX_ENTITY_LENGTH: usize = 64;
+    loop {
+        if !stream.pending.is_empty() {
+            let length = stream.pending.len().min(output.len());
+            output[..length].copy_from_slice(&stream.pending[..length]);
+            stream.pending.drain(..length);
+            return Ok(length);
+        }
+        if !stream.parser.text_pending || output.is_empty() {
+            return Ok(0);
+        }
+        let parser = &mut stream.parser.parser;
+        let available = fill_stream(parser.reader.get_mut()).await?;
+        if available.is_empty() {
+            return Err(eof_error(parser));
+        }
+        if stream.entity.is_empty() {
+            let length = available
+                .iter()
+                .position(|byte| matches!(byte, b'&' | b'<'))
+                .unwrap_or(available.len())
+                .min(output.len());
+            if length > 0 {
+                output[..length].copy_from_slice(&available[..length]);
+                consume_stream(parser, length);
+                if parser.options.verify_sha1 {
+                    stream.hasher.update(&output[..length]);
+                }
+                return Ok(length);
+            }
+            if available[0] == b'<' {
+                stream.parser.text_pending = false;
+                continue;
+            }
+            stream.entity.push(b'&');
+            consume_stream(parser, 1);
+            continue;
+        }
+        let end = available
+            .iter()
+            .position(|byte| matches!(byte, b'&' | b';' | b'<'));
+        let length = match end {
+            Some(end) if available[end] == b';' => end + 1,
+            Some(end) => end,
+            None => available.len(),
+        }
+        .min(MAX_ENTITY_LENGTH + 1 - stream.entity.len());
+        stream.entity.extend_from_slice(&available[..length]);
+        consume_stream(parser, length);
+        if stream.entity.ends_with(b";") || stream.entity.len() > MAX_ENTITY_LENGTH || end.is_some()
+        {
+            let entity = match std::str::from_utf8(&stream.entity) {
+                Err(error) => {
+                    return Err(Error::XmlReader(
+                        Box::new(EncodingError::from(error).into()),
+                        location(parser),
+                    ));
+                }
+                Ok(entity) => entity,
+            };
+            match quick_xml::escape::unescape(entity) {
+                Err(_) if parser.options.keep_unknown_entities => {
+                    stream.pending.extend_from_slice(entity.as_bytes())
+                }
+                Err(error) => return Err(Error::Escape(Box::new(error), location(parser))),
+                Ok(text) => stream.pending.extend_from_slice(text.as_bytes()),
+            }
+            if parser.options.verify_sha1 {
+                stream.hasher.update(&stream.pending);
+            }
+            stream.entity.clear();
+        }
+    }
+}
+
+async fn read_child<T: ChildElement>(parser: &mut Parser<impl Input>) -> Result<Option<T>, Error> {
+    loop {
+        prepare_event(parser).await?;
+        return Ok(Some(
+            match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+                (_, Event::End(_)) => {
+                    pop_path(&mut parser.path);
+                    return Ok(None);
+                }
+                (_, Event::Eof) => return Err(eof_error(parser)),
+                (namespace, Event::Start(event)) => {
+                    push_path(&mut parser.path, event.local_name().as_ref());
+                    if match_namespace(parser.options.lenient_namespaces, namespace) {
+                        let start = parser.text_buffer.len();
+                        let element = T::from_start(&event, &mut parser.text_buffer)?;
+                        record_text_location(parser, start);
+                        element
+                    } else {
+                        T::UNKNOWN
+                    }
+                }
+                _ => continue,
+            },
+        ));
+    }
+}
+
+fn record_text_location(parser: &mut Parser<impl Input>, start: usize) {
+    // Text is unescaped after the end of its page or revision, so the location of each element whose text and attributes are stored is recorded to report errors found then.
+    let source = parser.reader.get_ref();
+    let path_start = parser.text_paths.len();
+    parser.text_paths.push_str(&parser.path);
+    parser.text_locations.push(TextLocation {
+        line: source.line,
+        line_start: source.line_start,
+        offset: source.offset,
+        path: path_start..parser.text_paths.len(),
+        start,
+    });
+}
+
+async fn recover(parser: &mut Parser<impl Input>, error: Error) -> Error {
+    let error = locate(parser, error);
+    // A revision exceeding the limit on the number of revisions has already been skipped.
+    if matches!(error, Error::LimitExceeded(Limit::Revisions, _)) {
+        return error;
+    }
+    // Without the start tag of the root element, the namespace of the following pages can't be resolved. Without recovery, the parser can't continue after an error.
+    if !parser.options.recovery || parser.root.is_empty() {
+        parser.finished = true;
+        return error;
+    }
+    let start = parser.reader.get_ref().offset;
+    let end = loop {
+        let delimiter = match skip_to_page_start(parser).await {
+            Err(error) => {
+                parser.finished = true;
+                return locate(parser, error);
+            }
+            Ok(None) => {
+                parser.finished = true;
+                break parser.reader.get_ref().offset;
+            }
+            Ok(Some(delimiter)) => delimiter,
+        };
+        // The XML reader can't continue after most errors, so a new one is created, reading the start tag of the root element again to resolve namespaces, followed by the part of the start tag of the page already consumed.
+        let source = parser.reader.get_mut();
+        let mut prefix = Vec::with_capacity(parser.root.len() + 8);
+        prefix.push(b'<');
+        prefix.extend_from_slice(&parser.root);
+        prefix.extend_from_slice(b"><page");
+        prefix.push(delimiter);
+        parser.reader = create_reader(Source {
+            line: source.line,
+            line_start: source.line_start,
+            offset: source.offset,
+            prefix,
+            reader: source.reader.take(),
+            tag_limit: None,
+        });
+        let page_start = parser.reader.get_ref().offset - 6;
+        if let Ok(true) = resume_page(parser).await {
+            parser.page_pending = true;
+            break page_start;
         }
     };
-    parser.buffer.clear();
-    if let Event::End(_) = parser
-        .reader
-        .read_resolved_event_into(&mut parser.buffer)?
+    Error::Skipped(Box::new(error), start..end)
+}
+
+async fn recover_history(parser: &mut HistoryParser<impl Input>, error: Error) -> Error {
+    let error = recover(&mut parser.parser, error).await;
+    // Nothing is left of the current page once parsing continues with the page found, or once the parser can't continue.
+    if parser.parser.finished || matches!(error, Error::Skipped(..)) {
+        parser.in_page = false;
+        parser.revision_pending = false;
+        parser.revision_streamed = false;
+        parser.text_pending = false;
+    }
+    error
+}
+
+async fn resume_page(parser: &mut Parser<impl Input>) -> Result<bool, Error> {
+    parser.path.clear();
+    prepare_event(parser).await?;
+    match Input::read_event(&mut parser.reader, &mut parser.buffer)
+        .await?
         .1
     {
-        Ok(text)
-    } else {
-        Err(Error::Format(
-            parser.reader.buffer_position().try_into().unwrap(),
-        ))
+        Event::Start(event) => push_path(&mut parser.path, event.local_name().as_ref()),
+        _ => return Ok(false),
+    }
+    prepare_event(parser).await?;
+    Ok(
+        match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+            (namespace, Event::Start(event)) => {
+                push_path(&mut parser.path, event.local_name().as_ref());
+                match_namespace(parser.options.lenient_namespaces, namespace)
+                    && event.local_name().as_ref() == b"page"
+            }
+            _ => false,
+        },
+    )
+}
+
+fn sha1_base36(data: &[u8]) -> [u8; 31] {
+    base36(sha1_smol::Sha1::from(data).digest())
+}
+
+async fn skip_streamed(parser: &mut HistoryParser<impl Input>) -> Result<(), Error> {
+    while parser.text_pending {
+        let available = fill_stream(parser.parser.reader.get_mut()).await?;
+        if available.is_empty() {
+            return Err(eof_error(&mut parser.parser));
+        }
+        let length = match available.iter().position(|byte| *byte == b'<') {
+            None => available.len(),
+            Some(end) => {
+                parser.text_pending = false;
+                end
+            }
+        };
+        consume_stream(&mut parser.parser, length);
+    }
+    if parser.revision_streamed {
+        parser.revision_streamed = false;
+        skip_element(&mut parser.parser).await?;
+        skip_element(&mut parser.parser).await?;
+    }
+    Ok(())
+}
+
+async fn skip_to_page_start(parser: &mut Parser<impl Input>) -> Result<Option<u8>, Error> {
+    const START: &[u8] = b"<page";
+    let source = parser.reader.get_mut();
+    source.prefix.clear();
+    let mut matched = 0;
+    loop {
+        let available = fill_stream(source).await?;
+        if available.is_empty() {
+            return Ok(None);
+        }
+        let mut length = 0;
+        let mut delimiter = None;
+        for &byte in available {
+            length += 1;
+            if matched == START.len() && (matches!(byte, b'/' | b'>') || byte.is_ascii_whitespace())
+            {
+                delimiter = Some(byte);
+                break;
+            }
+            matched = if matched < START.len() && byte == START[matched] {
+                matched + 1
+            } else {
+                usize::from(byte == b'<')
+            };
+        }
+        source.consume(length);
+        if delimiter.is_some() {
+            return Ok(delimiter);
+        }
     }
 }
 
-fn skip_element(parser: &mut Parser<impl BufRead>) -> Result<(), quick_xml::Error> {
+async fn skip_element(parser: &mut Parser<impl Input>) -> Result<(), Error> {
     let mut level = 0;
     loop {
-        parser.buffer.clear();
-        match parser
-            .reader
-            .read_resolved_event_into(&mut parser.buffer)?
+        prepare_event(parser).await?;
+        match Input::read_event(&mut parser.reader, &mut parser.buffer)
+            .await?
             .1
         {
             Event::End(_) => {
                 if level == 0 {
+                    pop_path(&mut parser.path);
                     return Ok(());
                 }
                 level -= 1;
@@ -388,3 +2897,148 @@ fn skip_element(parser: &mut Parser<impl BufRead>) -> Result<(), quick_xml::Erro
         }
     }
 }
+
+async fn start(parser: &mut Parser<impl Input>) -> Result<SchemaVersion, Error> {
+    if let Some(version) = parser.schema_version {
+        return Ok(version);
+    }
+    let version = loop {
+        prepare_event(parser).await?;
+        match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+            (_, Event::Eof) => return Err(eof_error(parser)),
+            (namespace, Event::Start(event)) => {
+                push_path(&mut parser.path, event.local_name().as_ref());
+                if let Some(version) = match_schema_version(namespace).or(parser
+                    .options
+                    .lenient_namespaces
+                    .then_some(SchemaVersion::V0_11))
+                    && event.local_name().as_ref() == b"mediawiki"
+                {
+                    break version;
+                }
+                return Err(format_error(parser));
+            }
+            _ => {}
+        }
+    };
+    parser.schema_version = Some(version);
+    parser.root.clone_from(&parser.buffer);
+    loop {
+        prepare_event(parser).await?;
+        match match Input::read_event(&mut parser.reader, &mut parser.buffer).await? {
+            (_, Event::End(_)) => {
+                pop_path(&mut parser.path);
+                parser.finished = true;
+                return Ok(version);
+            }
+            (_, Event::Eof) => return Err(eof_error(parser)),
+            (namespace, Event::Start(event)) => {
+                push_path(&mut parser.path, event.local_name().as_ref());
+                if match_namespace(parser.options.lenient_namespaces, namespace) {
+                    match event.local_name().as_ref() {
+                        b"page" => Some(true),
+                        b"siteinfo" => Some(false),
+                        _ => None,
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => continue,
+        } {
+            None => skip_element(parser).await?,
+            Some(true) => parser.page_pending = true,
+            Some(false) => parser.site_info = Some(parse_site_info(parser).await?),
+        }
+        return Ok(version);
+    }
+}
+
+fn text_location(parser: &Parser<impl Input>, start: usize) -> Location {
+    // The text belongs to the last element recorded at or before its start. Elements recorded after the buffer was truncated are found before the ones they replace.
+    match parser
+        .text_locations
+        .iter()
+        .rev()
+        .find(|text| text.start <= start)
+    {
+        None => location(parser),
+        Some(text) => location_at(
+            parser,
+            text.line,
+            text.line_start,
+            text.offset,
+            &parser.text_paths[text.path.clone()],
+        ),
+    }
+}
+
+fn unescape_known_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // An entity ends at the first semicolon, unless another entity starts before it.
+        let length = match rest[1..].find(['&', ';']) {
+            Some(end) if rest.as_bytes()[end + 1] == b';' => end + 2,
+            _ => 1,
+        };
+        match quick_xml::escape::unescape(&rest[..length]) {
+            Err(_) => output.push_str(&rest[..length]),
+            Ok(text) => output.push_str(&text),
+        }
+        rest = &rest[length..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn verify_sha1<S: AsRef<str>>(
+    parser: &Parser<impl Input>,
+    revision: &Revision<S>,
+    sha1_range: Option<Range<usize>>,
+    digest: Option<sha1_smol::Digest>,
+) -> Result<(), Error> {
+    let sha1 = match &revision.sha1 {
+        Some(sha1)
+            if parser.options.verify_sha1
+                && !parser.options.skip_text
+                && !sha1.as_ref().is_empty()
+                && !revision.text_deleted
+                && !revision.slots.iter().any(|slot| slot.text_deleted) =>
+        {
+            sha1
+        }
+        _ => return Ok(()),
+    };
+    let digest = match digest {
+        None => sha1_smol::Sha1::from(revision.text.as_ref()).digest(),
+        Some(digest) => digest,
+    };
+    // Combined the same way as `RevisionSlots::computeSha1` in Mediawiki.
+    let mut slots = std::iter::once(("main", base36(digest)))
+        .chain(revision.slots.iter().map(|slot| {
+            (
+                slot.role.as_ref(),
+                sha1_base36(slot.text.as_ref().as_bytes()),
+            )
+        }))
+        .collect::<Vec<_>>();
+    slots.sort_by_key(|(role, _)| *role);
+    let mut expected = slots[0].1;
+    for (_, hash) in &slots[1..] {
+        expected = sha1_base36(&[expected, *hash].concat());
+    }
+    if sha1.as_ref().as_bytes().eq_ignore_ascii_case(&expected) {
+        Ok(())
+    } else {
+        Err(Error::Sha1Mismatch(
+            revision.id,
+            match sha1_range {
+                None => location(parser),
+                Some(range) => text_location(parser, range.start),
+            },
+        ))
+    }
+}
@@ -0,0 +1,328 @@
+//! Random access into multistream bzip2 dumps.
+
+use crate::{Error, Location, Page, Parser, ParserBuilder};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read, Seek, SeekFrom},
+    path::Path,
+    sync::Arc,
+};
+
+/// Multistream bzip2 dump opened for random access.
+///
+/// A multistream dump, such as the Wikimedia dumps with file names ending with `-multistream.xml.bz2`, is a concatenation of independent bzip2 streams. The first stream contains the beginning of the `mediawiki` element and the `siteinfo` element, and each following stream contains the `page` elements of about 100 pages. With the offsets of the streams from a `MultistreamIndex`, a page is parsed by decompressing only the stream containing it.
+pub struct MultistreamDump<R> {
+    header: Arc<[u8]>,
+    index: MultistreamIndex,
+    source: R,
+}
+
+/// Iterator over the pages of a multistream dump, parsed in parallel.
+///
+/// Created by `MultistreamDump::par_pages` or `MultistreamDump::par_pages_unordered`. The compressed streams are read one at a time from the dump, and decompressed and parsed in the global thread pool of `rayon`, with at most two streams per thread read ahead.
+#[cfg(feature = "rayon")]
+pub struct ParallelPages<'a, R> {
+    block_count: usize,
+    dump: &'a mut MultistreamDump<R>,
+    finished: std::collections::BTreeMap<usize, Vec<Result<Page, Error>>>,
+    in_flight: usize,
+    next_block: usize,
+    next_finished: usize,
+    ordered: bool,
+    pages: std::vec::IntoIter<Result<Page, Error>>,
+    receiver: std::sync::mpsc::Receiver<(usize, Vec<Result<Page, Error>>)>,
+    sender: std::sync::mpsc::Sender<(usize, Vec<Result<Page, Error>>)>,
+}
+
+/// Index of a multistream bzip2 dump.
+///
+/// Parsed from the index published along with the dump, such as the Wikimedia dumps with file names ending with `-multistream-index.txt.bz2`. Each line of the index is in the format `offset:id:title`, where `offset` is the position in bytes of the bzip2 stream containing the page within the compressed dump.
+#[derive(Debug, Default)]
+pub struct MultistreamIndex {
+    ids: HashMap<u64, u64>,
+    offsets: Vec<u64>,
+    titles: HashMap<String, u64>,
+}
+
+impl MultistreamDump<std::io::BufReader<std::fs::File>> {
+    /// Opens a multistream dump and its index.
+    ///
+    /// The index is decompressed with `decompress` if it's compressed.
+    pub fn open(dump: impl AsRef<Path>, index: impl AsRef<Path>) -> Result<Self, Error> {
+        let index = MultistreamIndex::open(index)?;
+        let dump = std::io::BufReader::new(std::fs::File::open(dump)?);
+        Self::new(dump, index)
+    }
+}
+
+impl<R: BufRead + Seek> MultistreamDump<R> {
+    /// Creates a multistream dump from the compressed dump and its index.
+    ///
+    /// Decompresses the first stream of the dump, which is prepended to each stream parsed afterwards so that the parser finds the namespace of the schema and the `siteinfo` element.
+    pub fn new(mut source: R, index: MultistreamIndex) -> Result<Self, Error> {
+        let mut header = read_stream(&mut source, 0)?;
+        header.truncate(find(&header, b"<page").unwrap_or(header.len()));
+        Ok(MultistreamDump {
+            header: header.into(),
+            index,
+            source,
+        })
+    }
+
+    /// Creates a parser for the pages in the stream at an offset.
+    ///
+    /// The offset is one of `MultistreamIndex::offsets`. The parser returns the pages in the stream in the order they appear in the dump.
+    pub fn block(&mut self, offset: u64) -> Result<Parser<std::io::Cursor<Vec<u8>>>, Error> {
+        let block = read_stream(&mut self.source, offset)?;
+        Ok(parse_block(ParserBuilder::new(), &self.header, &block))
+    }
+
+    /// The index of the dump.
+    pub fn index(&self) -> &MultistreamIndex {
+        &self.index
+    }
+
+    /// Parses the page with an identifier.
+    ///
+    /// Returns `None` if the page is not in the index or not in the stream the index points to. Other pages in the stream are skipped without parsing their revisions.
+    pub fn page_by_id(&mut self, id: u64) -> Result<Option<Page>, Error> {
+        let Some(offset) = self.index.offset_by_id(id) else {
+            return Ok(None);
+        };
+        let block = read_stream(&mut self.source, offset)?;
+        let builder = ParserBuilder::new().filter(move |header| header.id == Some(id));
+        parse_block(builder, &self.header, &block)
+            .next()
+            .transpose()
+    }
+
+    /// Parses the page with a title.
+    ///
+    /// Returns `None` if the page is not in the index or not in the stream the index points to. Other pages in the stream are skipped without parsing their revisions.
+    pub fn page_by_title(&mut self, title: &str) -> Result<Option<Page>, Error> {
+        let Some(offset) = self.index.offset_by_title(title) else {
+            return Ok(None);
+        };
+        let block = read_stream(&mut self.source, offset)?;
+        let title = title.to_owned();
+        let builder = ParserBuilder::new().filter(move |header| header.title == title);
+        parse_block(builder, &self.header, &block)
+            .next()
+            .transpose()
+    }
+
+    /// Parses every page in the dump in parallel, in the order they appear in the dump.
+    ///
+    /// Only the streams at `MultistreamIndex::offsets` are parsed, so pages not in the index are not returned.
+    #[cfg(feature = "rayon")]
+    pub fn par_pages(&mut self) -> ParallelPages<'_, R> {
+        ParallelPages::new(self, true)
+    }
+
+    /// Parses every page in the dump in parallel, in the order the streams containing them are parsed.
+    ///
+    /// Pages in the same stream are returned in the order they appear in the dump. Only the streams at `MultistreamIndex::offsets` are parsed, so pages not in the index are not returned.
+    #[cfg(feature = "rayon")]
+    pub fn par_pages_unordered(&mut self) -> ParallelPages<'_, R> {
+        ParallelPages::new(self, false)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, R: BufRead + Seek> ParallelPages<'a, R> {
+    fn new(dump: &'a mut MultistreamDump<R>, ordered: bool) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        ParallelPages {
+            block_count: dump.index.offsets.len(),
+            dump,
+            finished: Default::default(),
+            in_flight: 0,
+            next_block: 0,
+            next_finished: 0,
+            ordered,
+            pages: vec![].into_iter(),
+            receiver,
+            sender,
+        }
+    }
+
+    fn read_block(&mut self) -> Result<Vec<u8>, Error> {
+        let offsets = &self.dump.index.offsets;
+        let offset = offsets[self.next_block];
+        if self.next_block == 0 {
+            self.dump.source.seek(SeekFrom::Start(offset))?;
+        }
+        let mut block = vec![];
+        match offsets.get(self.next_block + 1) {
+            None => self.dump.source.read_to_end(&mut block)?,
+            Some(end) => (&mut self.dump.source)
+                .take(end - offset)
+                .read_to_end(&mut block)?,
+        };
+        Ok(block)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<R: BufRead + Seek> Iterator for ParallelPages<'_, R> {
+    type Item = Result<Page, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(page) = self.pages.next() {
+                return Some(page);
+            }
+            // Streams parsed before the ones preceding them wait in `finished`, and count as read ahead until they're returned.
+            while self.in_flight + self.finished.len() < 2 * rayon::current_num_threads()
+                && self.next_block < self.block_count
+            {
+                let index = self.next_block;
+                match self.read_block() {
+                    Err(error) => {
+                        self.finished.insert(index, vec![Err(error)]);
+                        self.next_block = self.block_count;
+                        if !self.ordered {
+                            self.next_finished = index;
+                        }
+                    }
+                    Ok(block) => {
+                        let header = self.dump.header.clone();
+                        let sender = self.sender.clone();
+                        rayon::spawn(move || {
+                            let pages = match decode_stream(&block[..]) {
+                                Err(error) => vec![Err(error)],
+                                Ok(block) => {
+                                    parse_block(ParserBuilder::new(), &header, &block).collect()
+                                }
+                            };
+                            let _ = sender.send((index, pages));
+                        });
+                        self.in_flight += 1;
+                        self.next_block += 1;
+                    }
+                }
+            }
+            if let Some(pages) = self.finished.remove(&self.next_finished) {
+                self.next_finished += 1;
+                self.pages = pages.into_iter();
+                continue;
+            }
+            if self.in_flight == 0 {
+                return None;
+            }
+            let (index, pages) = self.receiver.recv().ok()?;
+            self.in_flight -= 1;
+            if self.ordered {
+                self.finished.insert(index, pages);
+            } else {
+                self.pages = pages.into_iter();
+            }
+        }
+    }
+}
+
+impl MultistreamIndex {
+    /// The offset of the stream containing the page with an identifier.
+    ///
+    /// `None` if the page is not in the index.
+    pub fn offset_by_id(&self, id: u64) -> Option<u64> {
+        self.ids.get(&id).copied()
+    }
+
+    /// The offset of the stream containing the page with a title.
+    ///
+    /// `None` if the page is not in the index.
+    pub fn offset_by_title(&self, title: &str) -> Option<u64> {
+        self.titles.get(title).copied()
+    }
+
+    /// The offsets of the streams containing pages, in ascending order and without duplicates.
+    pub fn offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+
+    /// Opens an index.
+    ///
+    /// The index is decompressed with `decompress` if it's compressed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Self::read(crate::decompress(file)?)
+    }
+
+    /// Reads an index from a stream.
+    ///
+    /// Titles containing entities are unescaped. Fails with `Error::Format` indicating the location of the line in the stream if a line is not in the format `offset:id:title`.
+    pub fn read(mut source: impl BufRead) -> Result<Self, Error> {
+        let mut index = MultistreamIndex::default();
+        let mut line = String::new();
+        let mut location = Location {
+            column: 1,
+            line: 1,
+            ..Default::default()
+        };
+        loop {
+            line.clear();
+            let length = source.read_line(&mut line)?;
+            if length == 0 {
+                break;
+            }
+            let mut fields = line.trim_end_matches(['\n', '\r']).splitn(3, ':');
+            let (Some(Ok(offset)), Some(Ok(id)), Some(title)) = (
+                fields.next().map(str::parse),
+                fields.next().map(str::parse),
+                fields.next(),
+            ) else {
+                return Err(Error::Format(location));
+            };
+            let title = match quick_xml::escape::unescape(title) {
+                Err(_) => title.to_owned(),
+                Ok(title) => title.into_owned(),
+            };
+            index.ids.insert(id, offset);
+            if index.offsets.last() != Some(&offset) {
+                index.offsets.push(offset);
+            }
+            index.titles.insert(title, offset);
+            location.line += 1;
+            location.offset += length as u64;
+        }
+        index.offsets.sort_unstable();
+        index.offsets.dedup();
+        Ok(index)
+    }
+}
+
+fn decode_stream(source: impl Read) -> Result<Vec<u8>, Error> {
+    let mut block = vec![];
+    bzip2::read::BzDecoder::new(source).read_to_end(&mut block)?;
+    Ok(block)
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+fn parse_block(
+    builder: ParserBuilder,
+    header: &[u8],
+    block: &[u8],
+) -> Parser<std::io::Cursor<Vec<u8>>> {
+    let start = find(block, b"<page").unwrap_or(block.len());
+    let end = block
+        .windows(7)
+        .rposition(|window| window == b"</page>")
+        .map_or(start, |end| end + 7)
+        .max(start);
+    let mut source = Vec::with_capacity(header.len() + end - start + 12);
+    source.extend_from_slice(header);
+    source.extend_from_slice(&block[start..end]);
+    source.extend_from_slice(b"</mediawiki>");
+    builder.parse(std::io::Cursor::new(source))
+}
+
+fn read_stream(source: &mut (impl Read + Seek), offset: u64) -> Result<Vec<u8>, Error> {
+    source.seek(SeekFrom::Start(offset))?;
+    decode_stream(source)
+}
@@ -1,10 +1,13 @@
 //! Defines a schema for elements from the wikipedia xml dump
+use crate::Timestamp;
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Hash)]
 #[serde(from = "i32")]
 /// Wikipedia namespace
 ///  see: <https://en.wikipedia.org/wiki/Wikipedia:Namespace>
+///
+/// Only the namespaces defined by MediaWiki itself, from -2 to 15, have a variant of their own. The numeric identifiers of the other namespaces are not the same on every wiki, for example 100 is `Portal` on the English Wikipedia but `Appendix` on the English Wiktionary, so they are `Namespace::Other`. The names used by the wiki a dump was exported from are available from `SiteInfo::namespaces`.
 pub enum Namespace {
     /// Can be used to link directly to a file, rather than to the file description page.
     Media,
@@ -13,6 +16,7 @@ pub enum Namespace {
     Special,
     /// All encyclopedia articles, lists, disambiguation pages, and encyclopedia redirects.
     /// Sometimes referred to as "mainspace" or "Article".
+    #[default]
     Main,
     /// Talk namespaces are used to discuss changes to pages
     Talk,
@@ -46,41 +50,40 @@ pub enum Namespace {
     Category,
     /// Talk page for category
     CategoryTalk,
-    /// Portals serve as enhanced "main pages" for specific broad subjects.
-    Portal,
-    /// Talk page for portal
-    PortalTalk,
-    /// Drafts are pages in the Draft namespace where new articles may be created and developed,
-    /// for a limited period of time.
-    Draft,
-    /// Talk page for draft pages
-    DraftTalk,
-    /// The TimedMediaHandler extension allows you to display audio and video files in wiki pages
-    TimedText,
-    /// Talk page for timed text
-    TimedTextTalk,
-    /// Where Wikipedia Lua source code is stored
-    Module,
-    /// Talk page for Module
-    ModuleTalk,
-    /// Depreciated by Wikipedia
-    /// A JavaScript program and/or a CSS snippet that can be enabled simply by checking an option
-    /// in a Wikipedia user's preferences
-    Gadget,
-    /// Depreciated by Wikipedia
-    /// Talk page for gadgets
-    GadgetTalk,
-    /// Depreciated by Wikipedia
-    /// Definition of gadgets
-    GadgetDefinition,
-    /// Depreciated by Wikipedia
-    /// Talk page for gadgets
-    GadgetDefinitionTalk,
-    /// Unknown namespace code
-    Unknown,
+    /// Namespace not listed above, with its numeric identifier
+    ///
+    /// Used for every namespace above 15, whose name is available from `SiteInfo::namespaces`.
+    Other(i32),
 }
 
 impl Namespace {
+    /// Names that MediaWiki accepts for the namespace on every wiki regardless of its language.
+    ///
+    /// Empty for the main namespace and for `Namespace::Other`.
+    pub fn canonical_names(&self) -> &'static [&'static str] {
+        match self {
+            Namespace::Media => &["Media"],
+            Namespace::Special => &["Special"],
+            Namespace::Talk => &["Talk"],
+            Namespace::User => &["User"],
+            Namespace::UserTalk => &["User talk"],
+            Namespace::Wikipedia => &["Project"],
+            Namespace::WikipediaTalk => &["Project talk"],
+            Namespace::File => &["File", "Image"],
+            Namespace::FileTalk => &["File talk", "Image talk"],
+            Namespace::MediaWiki => &["MediaWiki"],
+            Namespace::MediaWikiTalk => &["MediaWiki talk"],
+            Namespace::Template => &["Template"],
+            Namespace::TemplateTalk => &["Template talk"],
+            Namespace::Help => &["Help"],
+            Namespace::HelpTalk => &["Help talk"],
+            Namespace::Category => &["Category"],
+            Namespace::CategoryTalk => &["Category talk"],
+            _ => &[],
+        }
+    }
+
+    /// The numeric identifier of the namespace.
     pub fn to_int(&self) -> i32 {
         match self {
             Namespace::Media => -2,
@@ -101,19 +104,7 @@ impl Namespace {
             Namespace::HelpTalk => 13,
             Namespace::Category => 14,
             Namespace::CategoryTalk => 15,
-            Namespace::Portal => 100,
-            Namespace::PortalTalk => 101,
-            Namespace::Draft => 118,
-            Namespace::DraftTalk => 119,
-            Namespace::TimedText => 710,
-            Namespace::TimedTextTalk => 711,
-            Namespace::Module => 828,
-            Namespace::ModuleTalk => 829,
-            Namespace::Gadget => 2300,
-            Namespace::GadgetTalk => 2301,
-            Namespace::GadgetDefinition => 2302,
-            Namespace::GadgetDefinitionTalk => 2303,
-            Namespace::Unknown => -999,
+            Namespace::Other(id) => *id,
         }
     }
 }
@@ -139,19 +130,7 @@ impl From<i32> for Namespace {
             13 => Namespace::HelpTalk,
             14 => Namespace::Category,
             15 => Namespace::CategoryTalk,
-            100 => Namespace::Portal,
-            101 => Namespace::PortalTalk,
-            118 => Namespace::Draft,
-            119 => Namespace::DraftTalk,
-            710 => Namespace::TimedText,
-            711 => Namespace::TimedTextTalk,
-            828 => Namespace::Module,
-            829 => Namespace::ModuleTalk,
-            2300 => Namespace::Gadget,
-            2301 => Namespace::GadgetTalk,
-            2302 => Namespace::GadgetDefinition,
-            2303 => Namespace::GadgetDefinitionTalk,
-            _ => Namespace::Unknown,
+            _ => Namespace::Other(id),
         }
     }
 }
@@ -194,7 +173,7 @@ pub struct Text {
 pub struct Revision {
     pub id: u32,
     pub parentid: Option<u32>,
-    pub timestamp: String,
+    pub timestamp: Timestamp,
     pub contributor: Contributer,
     pub minor: Option<Minor>,
     pub comment: Option<String>,
@@ -0,0 +1,169 @@
+//! Parse the timestamps of revisions.
+
+use serde::Deserialize;
+
+/// Time of a revision in UTC.
+///
+/// Parsed from the text content of the `timestamp` element, which Mediawiki always writes in the ISO 8601 format `YYYY-MM-DDTHH:MM:SSZ`.
+///
+/// With the cargo feature `chrono` enabled, converts into `chrono::DateTime<chrono::Utc>`. With the cargo feature `time` enabled, converts into `time::OffsetDateTime`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[serde(try_from = "String")]
+pub struct Timestamp {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+/// The error type for parsing a `Timestamp`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimestampError;
+
+impl Timestamp {
+    /// The day of the month, from 1 to 31.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from 0 to 23.
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from 0 to 59.
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The second, from 0 to 59.
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// The number of seconds since 1970-01-01T00:00:00Z.
+    pub fn unix_timestamp(&self) -> i64 {
+        // Days from civil, see <http://howardhinnant.github.io/date_algorithms.html>.
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year =
+            (153 * ((i64::from(self.month) + 9) % 12) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        days * 86400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+    }
+
+    /// The year, from 0 to 9999.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl std::fmt::Display for TimestampError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("Timestamp not in the format YYYY-MM-DDTHH:MM:SSZ")
+    }
+}
+
+impl std::error::Error for TimestampError {}
+
+impl std::str::FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let bytes = value.as_bytes();
+        if bytes.len() != 20
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || bytes[10] != b'T'
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+            || bytes[19] != b'Z'
+        {
+            return Err(TimestampError);
+        }
+        let number = |start: usize, end: usize| {
+            bytes[start..end].iter().try_fold(0, |number: u16, digit| {
+                if digit.is_ascii_digit() {
+                    Ok(number * 10 + u16::from(digit - b'0'))
+                } else {
+                    Err(TimestampError)
+                }
+            })
+        };
+        let year = number(0, 4)?;
+        let month = number(5, 7)? as u8;
+        let day = number(8, 10)? as u8;
+        let hour = number(11, 13)? as u8;
+        let minute = number(14, 16)? as u8;
+        let second = number(17, 19)? as u8;
+        let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            2 if leap_year => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return Err(TimestampError),
+        };
+        if day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 59 {
+            return Err(TimestampError);
+        }
+        Ok(Timestamp {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = TimestampError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(value: Timestamp) -> Self {
+        chrono::NaiveDate::from_ymd_opt(value.year.into(), value.month.into(), value.day.into())
+            .and_then(|date| {
+                date.and_hms_opt(value.hour.into(), value.minute.into(), value.second.into())
+            })
+            .expect("timestamp validated when parsed")
+            .and_utc()
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Timestamp> for time::OffsetDateTime {
+    fn from(value: Timestamp) -> Self {
+        time::OffsetDateTime::from_unix_timestamp(value.unix_timestamp())
+            .expect("timestamp validated when parsed")
+    }
+}
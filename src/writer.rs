@@ -0,0 +1,247 @@
+//! Write dumps in the export format of Mediawiki.
+
+use crate::{Contributor, Page, PageHeader, Revision, SchemaVersion, SiteInfo, Slot};
+use quick_xml::escape::{escape, partial_escape};
+use std::io::Write;
+
+/// Writer of XML dumps in the format exported by Mediawiki.
+///
+/// Created by `Writer::new`, which writes the beginning of the `mediawiki` element and the `siteinfo` element. Pages are written with `Writer::write_page`, or with `Writer::write_page_start`, `Writer::write_revision` and `Writer::write_page_end` for pages with any number of revisions, and the dump is completed by `Writer::finish`.
+///
+/// The dump conforms to version 0.10 or 0.11 of the export schema and can be parsed by `parse` and imported by Mediawiki. Fields that are `None` are left out, including those defined as mandatory in the schema, so that parsing the dump gives back the same pages.
+pub struct Writer<W: Write> {
+    version: SchemaVersion,
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Completes the dump and returns the underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.writer.write_all(b"</mediawiki>\n")?;
+        Ok(self.writer)
+    }
+
+    /// Creates a writer and writes the beginning of the dump.
+    ///
+    /// Versions of the schema before 0.10 are written as version 0.10. Additional slots of revisions can only be represented in version 0.11, and are left out in version 0.10. The `siteinfo` element is written if `site_info` is not `None`.
+    pub fn new(
+        mut writer: W,
+        version: SchemaVersion,
+        site_info: Option<&SiteInfo>,
+    ) -> std::io::Result<Self> {
+        let version = version.max(SchemaVersion::V0_10);
+        write!(
+            writer,
+            concat!(
+                r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-{0}/""#,
+                r#" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance""#,
+                r#" xsi:schemaLocation="http://www.mediawiki.org/xml/export-{0}/ http://www.mediawiki.org/xml/export-{0}.xsd""#,
+                r#" version="{0}">"#,
+                "\n"
+            ),
+            version
+        )?;
+        if let Some(site_info) = site_info {
+            write_site_info(&mut writer, site_info)?;
+        }
+        Ok(Writer { version, writer })
+    }
+
+    /// Writes a page with its revision.
+    pub fn write_page(&mut self, page: &Page<impl AsRef<str>>) -> std::io::Result<()> {
+        self.write_page_start(&PageHeader {
+            id: page.id,
+            namespace: page.namespace,
+            redirect: page.redirect.as_ref().map(AsRef::as_ref),
+            restrictions: page.restrictions.as_ref().map(AsRef::as_ref),
+            title: page.title.as_ref(),
+        })?;
+        self.write_revision(&page.revision)?;
+        self.write_page_end()
+    }
+
+    /// Writes the end of a page started with `Writer::write_page_start`.
+    pub fn write_page_end(&mut self) -> std::io::Result<()> {
+        self.writer.write_all(b"  </page>\n")
+    }
+
+    /// Writes the beginning of a page, to be followed by its revisions written with `Writer::write_revision` and by `Writer::write_page_end`.
+    pub fn write_page_start(
+        &mut self,
+        header: &PageHeader<impl AsRef<str>>,
+    ) -> std::io::Result<()> {
+        self.writer.write_all(b"  <page>\n")?;
+        write_element(&mut self.writer, 4, "title", Some(header.title.as_ref()))?;
+        write_element(
+            &mut self.writer,
+            4,
+            "ns",
+            Some(&header.namespace.to_int().to_string()),
+        )?;
+        write_element(
+            &mut self.writer,
+            4,
+            "id",
+            header.id.map(|id| id.to_string()),
+        )?;
+        if let Some(redirect) = &header.redirect {
+            writeln!(
+                self.writer,
+                r#"    <redirect title="{}" />"#,
+                escape(redirect.as_ref())
+            )?;
+        }
+        write_element(
+            &mut self.writer,
+            4,
+            "restrictions",
+            header.restrictions.as_ref(),
+        )
+    }
+
+    /// Writes a revision of a page started with `Writer::write_page_start`.
+    pub fn write_revision(&mut self, revision: &Revision<impl AsRef<str>>) -> std::io::Result<()> {
+        let writer = &mut self.writer;
+        writer.write_all(b"    <revision>\n")?;
+        write_element(writer, 6, "id", revision.id.map(|id| id.to_string()))?;
+        write_element(
+            writer,
+            6,
+            "parentid",
+            revision.parent_id.map(|id| id.to_string()),
+        )?;
+        write_element(
+            writer,
+            6,
+            "timestamp",
+            revision.timestamp.map(|timestamp| timestamp.to_string()),
+        )?;
+        if let Some(contributor) = &revision.contributor {
+            write_contributor(writer, contributor)?;
+        }
+        if revision.minor {
+            writer.write_all(b"      <minor />\n")?;
+        }
+        if revision.comment_deleted {
+            writer.write_all(b"      <comment deleted=\"deleted\" />\n")?;
+        } else {
+            write_element(writer, 6, "comment", revision.comment.as_ref())?;
+        }
+        if self.version >= SchemaVersion::V0_11 {
+            write_element(
+                writer,
+                6,
+                "origin",
+                revision.origin.map(|id| id.to_string()),
+            )?;
+        }
+        write_element(writer, 6, "model", revision.model.as_ref())?;
+        write_element(writer, 6, "format", revision.format.as_ref())?;
+        write_text(
+            writer,
+            6,
+            revision.text.as_ref(),
+            revision.text_bytes,
+            revision.text_deleted,
+        )?;
+        if self.version >= SchemaVersion::V0_11 {
+            for slot in &revision.slots {
+                write_slot(writer, slot)?;
+            }
+        }
+        write_element(writer, 6, "sha1", revision.sha1.as_ref())?;
+        writer.write_all(b"    </revision>\n")
+    }
+}
+
+fn write_contributor(
+    writer: &mut impl Write,
+    contributor: &Contributor<impl AsRef<str>>,
+) -> std::io::Result<()> {
+    if contributor.deleted {
+        return writer.write_all(b"      <contributor deleted=\"deleted\" />\n");
+    }
+    writer.write_all(b"      <contributor>\n")?;
+    write_element(writer, 8, "username", contributor.username.as_ref())?;
+    write_element(writer, 8, "id", contributor.id.map(|id| id.to_string()))?;
+    write_element(writer, 8, "ip", contributor.ip.as_ref())?;
+    writer.write_all(b"      </contributor>\n")
+}
+
+fn write_element(
+    writer: &mut impl Write,
+    indent: usize,
+    name: &str,
+    text: Option<impl AsRef<str>>,
+) -> std::io::Result<()> {
+    match text {
+        None => Ok(()),
+        Some(text) => writeln!(
+            writer,
+            "{:indent$}<{name}>{}</{name}>",
+            "",
+            partial_escape(text.as_ref())
+        ),
+    }
+}
+
+fn write_site_info(writer: &mut impl Write, site_info: &SiteInfo) -> std::io::Result<()> {
+    writer.write_all(b"  <siteinfo>\n")?;
+    write_element(writer, 4, "sitename", site_info.sitename.as_ref())?;
+    write_element(writer, 4, "dbname", site_info.dbname.as_ref())?;
+    write_element(writer, 4, "base", site_info.base.as_ref())?;
+    write_element(writer, 4, "generator", site_info.generator.as_ref())?;
+    write_element(writer, 4, "case", site_info.case.map(|case| case.as_str()))?;
+    writer.write_all(b"    <namespaces>\n")?;
+    for namespace in site_info.namespaces.iter() {
+        write!(writer, r#"      <namespace key="{}""#, namespace.key)?;
+        if let Some(case) = namespace.case {
+            write!(writer, r#" case="{}""#, case.as_str())?;
+        }
+        if namespace.name.is_empty() {
+            writer.write_all(b" />\n")?;
+        } else {
+            writeln!(writer, ">{}</namespace>", partial_escape(&namespace.name))?;
+        }
+    }
+    writer.write_all(b"    </namespaces>\n")?;
+    writer.write_all(b"  </siteinfo>\n")
+}
+
+fn write_slot(writer: &mut impl Write, slot: &Slot<impl AsRef<str>>) -> std::io::Result<()> {
+    writer.write_all(b"      <content>\n")?;
+    write_element(writer, 8, "role", Some(slot.role.as_ref()))?;
+    write_element(writer, 8, "origin", slot.origin.map(|id| id.to_string()))?;
+    write_element(writer, 8, "model", slot.model.as_ref())?;
+    write_element(writer, 8, "format", slot.format.as_ref())?;
+    write_text(
+        writer,
+        8,
+        slot.text.as_ref(),
+        slot.text_bytes,
+        slot.text_deleted,
+    )?;
+    writer.write_all(b"      </content>\n")
+}
+
+fn write_text(
+    writer: &mut impl Write,
+    indent: usize,
+    text: &str,
+    bytes: Option<u64>,
+    deleted: bool,
+) -> std::io::Result<()> {
+    write!(writer, "{:indent$}<text", "")?;
+    if let Some(bytes) = bytes {
+        write!(writer, r#" bytes="{bytes}""#)?;
+    }
+    if deleted {
+        writer.write_all(b" deleted=\"deleted\" />\n")
+    } else {
+        writeln!(
+            writer,
+            r#" xml:space="preserve">{}</text>"#,
+            partial_escape(text)
+        )
+    }
+}
@@ -3,7 +3,7 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
-use parse_mediawiki_dump_reboot::schema::Namespace;
+use parse_mediawiki_dump_reboot::{Case, schema::Namespace};
 
 extern crate parse_mediawiki_dump_reboot;
 
@@ -34,23 +34,1794 @@ fn main() {
         parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
     assert!(match parser.next() {
         Some(Ok(parse_mediawiki_dump_reboot::Page {
-            format: Some(format),
-            model: Some(model),
+            id: None,
             namespace: Namespace::Main,
-            text,
+            redirect: None,
+            restrictions: None,
+            revision:
+                parse_mediawiki_dump_reboot::Revision {
+                    format: Some(format),
+                    model: Some(model),
+                    text,
+                    ..
+                },
             title,
         })) => format == "beta" && model == "gamma" && text == "delta" && title == "alpha",
         _ => false,
     });
     assert!(match parser.next() {
         Some(Ok(parse_mediawiki_dump_reboot::Page {
-            format: None,
-            model: None,
+            id: None,
             namespace: Namespace::Wikipedia,
-            text,
+            redirect: None,
+            restrictions: None,
+            revision:
+                parse_mediawiki_dump_reboot::Revision {
+                    format: None,
+                    model: None,
+                    text,
+                    ..
+                },
             title,
         })) => text == "zeta" && title == "epsilon",
         _ => false,
     });
     assert!(parser.next().is_none());
 }
+
+const HISTORY_DUMP: &str = concat!(
+    r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+    "<page>",
+    "<title>alpha</title>",
+    "<ns>0</ns>",
+    "<revision>",
+    "<text>beta</text>",
+    "</revision>",
+    "<revision>",
+    "<model>gamma</model>",
+    "<text>delta</text>",
+    "</revision>",
+    "</page>",
+    "<page>",
+    "<title>epsilon</title>",
+    "<ns>4</ns>",
+    "<revision>",
+    "<text>zeta</text>",
+    "</revision>",
+    "<revision>",
+    "<text>eta</text>",
+    "</revision>",
+    "</page>",
+    "<page>",
+    "<title>theta</title>",
+    "<ns>0</ns>",
+    "<revision>",
+    "<text>iota</text>",
+    "</revision>",
+    "</page>",
+    "</mediawiki>"
+);
+
+#[test]
+fn history() {
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(std::io::BufReader::new(
+        std::io::Cursor::new(HISTORY_DUMP),
+    ));
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(page.namespace == Namespace::Main && page.title == "alpha");
+    assert!(match page.next() {
+        Some(Ok(parse_mediawiki_dump_reboot::Revision {
+            format: None,
+            model: None,
+            text,
+            ..
+        })) => text == "beta",
+        _ => false,
+    });
+    assert!(match page.next() {
+        Some(Ok(parse_mediawiki_dump_reboot::Revision {
+            format: None,
+            model: Some(model),
+            text,
+            ..
+        })) => model == "gamma" && text == "delta",
+        _ => false,
+    });
+    assert!(page.next().is_none());
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(page.namespace == Namespace::Wikipedia && page.title == "epsilon");
+    assert!(matches!(page.next(), Some(Ok(revision)) if revision.text == "zeta"));
+    let page = parser.next_page().unwrap().unwrap();
+    assert!(page.title == "theta");
+    assert_eq!(
+        page.map(|revision| revision.unwrap().text)
+            .collect::<Vec<_>>(),
+        ["iota"]
+    );
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn multiple_revisions_not_supported() {
+    let mut parser = parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(
+        std::io::Cursor::new(HISTORY_DUMP),
+    ));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::NotSupported(_)))
+    ));
+}
+
+#[test]
+fn site_info() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<siteinfo>",
+        "<sitename>Wiktionnaire</sitename>",
+        "<dbname>frwiktionary</dbname>",
+        "<base>https://fr.wiktionary.org/wiki/Wiktionnaire:Page_d%E2%80%99accueil</base>",
+        "<generator>MediaWiki 1.42.0-wmf.5</generator>",
+        "<case>case-sensitive</case>",
+        "<namespaces>",
+        r#"<namespace key="-2" case="case-sensitive">Média</namespace>"#,
+        r#"<namespace key="0" case="case-sensitive" />"#,
+        r#"<namespace key="100" case="case-sensitive">Annexe</namespace>"#,
+        r#"<namespace key="106" case="case-insensitive">Thésaurus</namespace>"#,
+        r#"<namespace key="108" case="gamma">Projet</namespace>"#,
+        "</namespaces>",
+        "</siteinfo>",
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<text>beta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let site_info = parser.site_info().unwrap().unwrap();
+    assert_eq!(site_info.sitename.as_deref(), Some("Wiktionnaire"));
+    assert_eq!(site_info.dbname.as_deref(), Some("frwiktionary"));
+    assert_eq!(
+        site_info.generator.as_deref(),
+        Some("MediaWiki 1.42.0-wmf.5")
+    );
+    assert_eq!(site_info.case, Some(Case::CaseSensitive));
+    assert_eq!(
+        site_info
+            .namespaces
+            .iter()
+            .map(|namespace| (namespace.key, namespace.case, namespace.name.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (-2, Some(Case::CaseSensitive), "Média"),
+            (0, Some(Case::CaseSensitive), ""),
+            (100, Some(Case::CaseSensitive), "Annexe"),
+            (106, Some(Case::CaseInsensitive), "Thésaurus"),
+            (108, None, "Projet")
+        ]
+    );
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "alpha"));
+    assert!(parser.next().is_none());
+    assert!(parser.next().is_none());
+    let dump = dump.replace("<case>case-sensitive</case>", "<case>delta</case>");
+    let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+    assert_eq!(parser.site_info().unwrap().unwrap().case, None);
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "alpha"));
+}
+
+#[test]
+fn namespaces() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<siteinfo>",
+        "<namespaces>",
+        r#"<namespace key="0" case="first-letter" />"#,
+        r#"<namespace key="2" case="first-letter">Utilisateur</namespace>"#,
+        r#"<namespace key="6" case="first-letter">Fichier</namespace>"#,
+        r#"<namespace key="100" case="first-letter">Annexe</namespace>"#,
+        r#"<namespace key="106" case="first-letter">Thésaurus</namespace>"#,
+        "</namespaces>",
+        "</siteinfo>",
+        "<page>",
+        "<title>Annexe:alpha</title>",
+        "<ns>106</ns>",
+        "<revision>",
+        "<text>beta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let namespaces = &parser.site_info().unwrap().unwrap().namespaces;
+    assert_eq!(namespaces.get(6).unwrap().aliases, ["File", "Image"]);
+    assert!(namespaces.get(106).unwrap().aliases.is_empty());
+    let lookup = |title| {
+        namespaces
+            .lookup(title)
+            .map(|(namespace, rest)| (namespace.key, rest))
+    };
+    assert_eq!(lookup("Annexe:Conjugaison"), Some((100, "Conjugaison")));
+    assert_eq!(lookup("thésaurus:chat"), Some((106, "chat")));
+    assert_eq!(lookup("User:Example"), Some((2, "Example")));
+    assert_eq!(lookup("Image_talk:Example"), None);
+    assert_eq!(lookup("Image:Example.png"), Some((6, "Example.png")));
+    assert_eq!(lookup("Le Seigneur des anneaux : Les Deux Tours"), None);
+    assert_eq!(
+        namespaces.get(106).unwrap().namespace(),
+        Namespace::Other(106)
+    );
+    assert_eq!(Namespace::Other(106).to_int(), 106);
+    assert!(matches!(
+        parser.next(),
+        Some(Ok(page)) if page.namespace == Namespace::Other(106)
+    ));
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.3/">"#,
+        "<siteinfo>",
+        "<sitename>Wiktionary</sitename>",
+        "<namespaces>",
+        r#"<namespace key="0" />"#,
+        r#"<namespace key="100">Appendix</namespace>"#,
+        "</namespaces>",
+        "</siteinfo>",
+        "<page>",
+        "<title>Appendix:Colors</title>",
+        "<revision><text>gamma</text></revision>",
+        "</page>",
+        "<page>",
+        "<title>Portal:Colors</title>",
+        "<revision><text>delta</text></revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+    let namespaces = &parser.site_info().unwrap().unwrap().namespaces;
+    assert_eq!(
+        namespaces.get(100).unwrap().namespace(),
+        Namespace::Other(100)
+    );
+    assert_eq!(Namespace::from(100), Namespace::Other(100));
+    assert_eq!(
+        parser.next().unwrap().unwrap().namespace,
+        Namespace::Other(100)
+    );
+    assert_eq!(parser.next().unwrap().unwrap().namespace, Namespace::Main);
+}
+
+#[test]
+fn page_metadata() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<id>12</id>",
+        r#"<redirect title="beta &amp; gamma" />"#,
+        "<restrictions>edit=sysop:move=sysop</restrictions>",
+        "<revision>",
+        "<text>#REDIRECT [[beta &amp; gamma]]</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let page = parser.next().unwrap().unwrap();
+    assert_eq!(page.id, Some(12));
+    assert_eq!(page.redirect.as_deref(), Some("beta & gamma"));
+    assert_eq!(page.restrictions.as_deref(), Some("edit=sysop:move=sysop"));
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn revision_metadata() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<id>12</id>",
+        "<revision>",
+        "<id>1001</id>",
+        "<parentid>1000</parentid>",
+        "<timestamp>2021-03-04T05:06:07Z</timestamp>",
+        "<contributor>",
+        "<username>Beta</username>",
+        "<id>42</id>",
+        "</contributor>",
+        "<minor />",
+        "<comment>gamma</comment>",
+        "<origin>1001</origin>",
+        "<model>wikitext</model>",
+        "<format>text/x-wiki</format>",
+        r#"<text bytes="5" xml:space="preserve">delta</text>"#,
+        "<sha1>dhfn5rwpw6yscp08efkmr9otbqvl3lz</sha1>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let revision = parser.next().unwrap().unwrap().revision;
+    assert_eq!(revision.id, Some(1001));
+    assert_eq!(revision.parent_id, Some(1000));
+    let timestamp = revision.timestamp.unwrap();
+    assert_eq!(timestamp.to_string(), "2021-03-04T05:06:07Z");
+    assert_eq!(timestamp.unix_timestamp(), 1614834367);
+    assert!(matches!(
+        revision.contributor,
+        Some(parse_mediawiki_dump_reboot::Contributor {
+            deleted: false,
+            id: Some(42),
+            ip: None,
+            username: Some(username),
+        }) if username == "Beta"
+    ));
+    assert!(revision.minor);
+    assert_eq!(revision.comment.as_deref(), Some("gamma"));
+    assert_eq!(revision.origin, Some(1001));
+    assert_eq!(revision.text, "delta");
+    assert_eq!(revision.text_bytes, Some(5));
+    assert_eq!(
+        revision.sha1.as_deref(),
+        Some("dhfn5rwpw6yscp08efkmr9otbqvl3lz")
+    );
+}
+
+#[test]
+fn timestamp() {
+    let timestamp = "2024-02-29T23:59:59Z"
+        .parse::<parse_mediawiki_dump_reboot::Timestamp>()
+        .unwrap();
+    assert_eq!(
+        (
+            timestamp.year(),
+            timestamp.month(),
+            timestamp.day(),
+            timestamp.hour(),
+            timestamp.minute(),
+            timestamp.second()
+        ),
+        (2024, 2, 29, 23, 59, 59)
+    );
+    assert_eq!(timestamp.unix_timestamp(), 1709251199);
+    for invalid in [
+        "2023-02-29T00:00:00Z",
+        "2023-13-01T00:00:00Z",
+        "2023-01-01T24:00:00Z",
+        "2023-01-01 00:00:00Z",
+        "2023-01-01T00:00:00",
+    ] {
+        assert!(
+            invalid
+                .parse::<parse_mediawiki_dump_reboot::Timestamp>()
+                .is_err()
+        );
+    }
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<timestamp>yesterday</timestamp>",
+        "<text>beta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::Timestamp(_)))
+    ));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn timestamp_chrono() {
+    let timestamp = "2021-03-04T05:06:07Z"
+        .parse::<parse_mediawiki_dump_reboot::Timestamp>()
+        .unwrap();
+    assert_eq!(
+        chrono::DateTime::<chrono::Utc>::from(timestamp).timestamp(),
+        1614834367
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn timestamp_time() {
+    let timestamp = "2021-03-04T05:06:07Z"
+        .parse::<parse_mediawiki_dump_reboot::Timestamp>()
+        .unwrap();
+    assert_eq!(
+        time::OffsetDateTime::from(timestamp).unix_timestamp(),
+        1614834367
+    );
+}
+
+#[test]
+fn verify_sha1() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<text>beta</text>",
+        "<sha1>izpd7ggt0ln78tvoog6pqvu1m7buz51</sha1>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>gamma</title>",
+        "<ns>0</ns>",
+        "<id>3</id>",
+        "<revision>",
+        "<id>4</id>",
+        "<text>delta</text>",
+        "<sha1>izpd7ggt0ln78tvoog6pqvu1m7buz51</sha1>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>epsilon</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<text>zeta</text>",
+        "<sha1>M3IE5WWBAXOBTIKEL8PWMS95I3FRISD</sha1>",
+        "</revision>",
+        "<revision>",
+        "<text />",
+        "<sha1>phoiac9h4m842xq45sp7s6u21eteeq1</sha1>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "alpha"));
+    let error = parser.next().unwrap().unwrap_err();
+    assert!(matches!(
+        &error,
+        parse_mediawiki_dump_reboot::Error::Sha1Mismatch(Some(4), location)
+            if location.page_id == Some(3) && location.page_title.as_deref() == Some("gamma")
+    ));
+    assert!(error.to_string().starts_with(
+        "The text of the revision with identifier 4 doesn't match its SHA-1 hash at line 1"
+    ));
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse_history(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert!(
+        parser
+            .next_page()
+            .unwrap()
+            .unwrap()
+            .all(|revision| revision.is_ok())
+    );
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(matches!(
+        page.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::Sha1Mismatch(Some(4), location)))
+            if location.page_title.as_deref() == Some("gamma")
+    ));
+    assert!(page.next().is_none());
+    let page = parser.next_page().unwrap().unwrap();
+    assert_eq!(
+        page.map(|revision| revision.unwrap().text)
+            .collect::<Vec<_>>(),
+        ["zeta", ""]
+    );
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn deleted() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        r#"<contributor deleted="deleted" />"#,
+        r#"<comment deleted="deleted" />"#,
+        r#"<text bytes="4" deleted="deleted" />"#,
+        "<sha1>izpd7ggt0ln78tvoog6pqvu1m7buz51</sha1>",
+        "</revision>",
+        "<revision>",
+        "<contributor><ip>192.0.2.1</ip></contributor>",
+        "<comment />",
+        r#"<text bytes="0" />"#,
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse_history(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let mut page = parser.next_page().unwrap().unwrap();
+    let revision = page.next().unwrap().unwrap();
+    assert!(matches!(
+        revision.contributor,
+        Some(parse_mediawiki_dump_reboot::Contributor {
+            deleted: true,
+            id: None,
+            ip: None,
+            username: None,
+        })
+    ));
+    assert!(revision.comment.is_none() && revision.comment_deleted);
+    assert!(revision.text.is_empty() && revision.text_deleted);
+    assert_eq!(revision.text_bytes, Some(4));
+    let revision = page.next().unwrap().unwrap();
+    assert!(matches!(
+        revision.contributor,
+        Some(parse_mediawiki_dump_reboot::Contributor {
+            deleted: false,
+            ip: Some(ip),
+            ..
+        }) if ip == "192.0.2.1"
+    ));
+    assert!(revision.comment.as_deref() == Some("") && !revision.comment_deleted);
+    assert!(revision.text.is_empty() && !revision.text_deleted);
+}
+
+#[test]
+fn schema_versions() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.3/" version="0.3">"#,
+        "<siteinfo>",
+        "<sitename>Wikipedia</sitename>",
+        "<base>http://en.wikipedia.org/wiki/Main_Page</base>",
+        "<generator>MediaWiki 1.13alpha</generator>",
+        "<case>first-letter</case>",
+        "<namespaces>",
+        r#"<namespace key="0" />"#,
+        r#"<namespace key="4">Wikipedia</namespace>"#,
+        r#"<namespace key="10">Template</namespace>"#,
+        "</namespaces>",
+        "</siteinfo>",
+        "<page>",
+        "<title>Wikipedia:alpha</title>",
+        "<id>1</id>",
+        "<revision>",
+        "<id>2</id>",
+        "<timestamp>2008-01-02T03:04:05Z</timestamp>",
+        "<contributor><ip>192.0.2.1</ip></contributor>",
+        r#"<text xml:space="preserve">beta</text>"#,
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>gamma</title>",
+        "<id>3</id>",
+        "<redirect />",
+        "<revision>",
+        "<id>4</id>",
+        r#"<text xml:space="preserve">#REDIRECT [[delta]]</text>"#,
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert_eq!(
+        parser.schema_version().unwrap(),
+        parse_mediawiki_dump_reboot::SchemaVersion::V0_3
+    );
+    let page = parser.next().unwrap().unwrap();
+    assert!(page.namespace == Namespace::Wikipedia && page.title == "Wikipedia:alpha");
+    let page = parser.next().unwrap().unwrap();
+    assert!(page.namespace == Namespace::Main && page.redirect.as_deref() == Some(""));
+    assert!(parser.next().is_none());
+    for version in ["0.1", "0.2"] {
+        let dump = format!(
+            concat!(
+                r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-{}/" version="{}">"#,
+                "<page>",
+                "<title>Talk:epsilon</title>",
+                "<id>5</id>",
+                "<restrictions>move=sysop</restrictions>",
+                "<revision>",
+                "<id>6</id>",
+                "<timestamp>2005-01-02T03:04:05Z</timestamp>",
+                "<contributor><username>zeta</username><id>7</id></contributor>",
+                "<minor />",
+                "<comment>eta</comment>",
+                "<text>theta</text>",
+                "</revision>",
+                "</page>",
+                "<page>",
+                "<title>image:iota.png</title>",
+                "<revision><text>kappa</text></revision>",
+                "</page>",
+                "<page>",
+                "<title>Lambda: mu</title>",
+                "<revision><text>nu</text></revision>",
+                "</page>",
+                "</mediawiki>"
+            ),
+            version, version
+        );
+        let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+        assert_eq!(parser.schema_version().unwrap().as_str(), version);
+        let page = parser.next().unwrap().unwrap();
+        assert_eq!(page.namespace, Namespace::Talk);
+        assert_eq!(page.restrictions.as_deref(), Some("move=sysop"));
+        assert!(page.revision.minor);
+        assert_eq!(page.revision.comment.as_deref(), Some("eta"));
+        assert_eq!(
+            page.revision.contributor.unwrap().username.as_deref(),
+            Some("zeta")
+        );
+        assert_eq!(page.revision.text, "theta");
+        assert_eq!(parser.next().unwrap().unwrap().namespace, Namespace::File);
+        assert_eq!(parser.next().unwrap().unwrap().namespace, Namespace::Main);
+        assert!(parser.next().is_none());
+    }
+    for version in ["0.8", "0.9"] {
+        let dump = format!(
+            concat!(
+                r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-{}/" version="{}">"#,
+                "<siteinfo>",
+                "<namespaces>",
+                r#"<namespace key="0" case="first-letter" />"#,
+                r#"<namespace key="2" case="first-letter">Utilisateur</namespace>"#,
+                "</namespaces>",
+                "</siteinfo>",
+                "<page>",
+                "<title>Utilisateur:xi</title>",
+                "<ns>2</ns>",
+                "<id>8</id>",
+                r#"<redirect title="omicron" />"#,
+                "<revision>",
+                "<id>10</id>",
+                "<parentid>9</parentid>",
+                "<timestamp>2014-01-02T03:04:05Z</timestamp>",
+                r#"<contributor deleted="deleted" />"#,
+                "<sha1>2gmtiv91fr1xg34jwyju7vp8jrq427o</sha1>",
+                "<model>wikitext</model>",
+                "<format>text/x-wiki</format>",
+                r#"<text xml:space="preserve" bytes="4">beta</text>"#,
+                "</revision>",
+                "</page>",
+                "</mediawiki>"
+            ),
+            version, version
+        );
+        let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+        assert_eq!(parser.schema_version().unwrap().as_str(), version);
+        let page = parser.next().unwrap().unwrap();
+        assert_eq!(page.namespace, Namespace::User);
+        assert_eq!(page.redirect.as_deref(), Some("omicron"));
+        assert_eq!(page.revision.parent_id, Some(9));
+        assert!(page.revision.contributor.unwrap().deleted);
+        assert_eq!(page.revision.model.as_deref(), Some("wikitext"));
+        assert_eq!(page.revision.format.as_deref(), Some("text/x-wiki"));
+        assert_eq!(page.revision.text_bytes, Some(4));
+        assert_eq!(page.revision.text, "beta");
+        assert!(parser.next().is_none());
+    }
+    let mut parser = parse_mediawiki_dump_reboot::parse(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-1.0/"></mediawiki>"#.as_bytes(),
+    );
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::Format(_)))
+    ));
+}
+
+#[test]
+fn slots() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+        "<page>",
+        "<title>File:Alpha.jpg</title>",
+        "<ns>6</ns>",
+        "<revision>",
+        "<origin>10</origin>",
+        "<model>wikitext</model>",
+        "<format>text/x-wiki</format>",
+        r#"<text bytes="4" xml:space="preserve">beta</text>"#,
+        "<sha1>2gmtiv91fr1xg34jwyju7vp8jrq427o</sha1>",
+        "<content>",
+        "<role>mediainfo</role>",
+        "<origin>11</origin>",
+        "<model>wikibase-mediainfo</model>",
+        "<format>application/json</format>",
+        r#"<text bytes="20" xml:space="preserve">{"type":"mediainfo"}</text>"#,
+        "</content>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let revision = parser.next().unwrap().unwrap().revision;
+    assert_eq!(revision.text, "beta");
+    assert!(match &revision.slots[..] {
+        [
+            parse_mediawiki_dump_reboot::Slot {
+                format: Some(format),
+                model: Some(model),
+                origin: Some(11),
+                role,
+                text,
+                text_bytes: Some(20),
+                text_deleted: false,
+            },
+        ] =>
+            format == "application/json"
+                && model == "wikibase-mediainfo"
+                && role == "mediainfo"
+                && text == r#"{"type":"mediainfo"}"#,
+        _ => false,
+    });
+}
+
+#[test]
+fn borrowed() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        r#"<redirect title="beta &amp; gamma" />"#,
+        "<revision>",
+        "<model>wikitext</model>",
+        "<text>delta &lt;br&gt;</text>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>epsilon</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<sha1>m3ie5wwbaxobtikel8pwms95i3frisd</sha1>",
+        "<text>zeta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let page = parser.next_borrowed().unwrap().unwrap();
+    assert!(matches!(page.title, std::borrow::Cow::Borrowed("alpha")));
+    assert!(matches!(
+        page.revision.model,
+        Some(std::borrow::Cow::Borrowed("wikitext"))
+    ));
+    assert!(
+        matches!(page.redirect, Some(std::borrow::Cow::Owned(ref redirect)) if redirect == "beta & gamma")
+    );
+    assert!(
+        matches!(page.revision.text, std::borrow::Cow::Owned(ref text) if text == "delta <br>")
+    );
+    let page = parser.next_borrowed().unwrap().unwrap();
+    assert!(matches!(page.title, std::borrow::Cow::Borrowed("epsilon")));
+    assert!(matches!(
+        page.revision.text,
+        std::borrow::Cow::Borrowed("zeta")
+    ));
+    assert!(parser.next_borrowed().is_none());
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(std::io::BufReader::new(
+        std::io::Cursor::new(HISTORY_DUMP),
+    ));
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(matches!(
+        page.next_borrowed(),
+        Some(Ok(revision)) if matches!(revision.text, std::borrow::Cow::Borrowed("beta"))
+    ));
+    assert!(matches!(
+        page.next_borrowed(),
+        Some(Ok(revision)) if revision.text == "delta"
+    ));
+    assert!(page.next_borrowed().is_none());
+}
+
+#[test]
+fn parse_into() {
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
+    let mut page = parse_mediawiki_dump_reboot::Page::default();
+    assert!(parser.parse_into(&mut page).unwrap());
+    assert!(page.namespace == Namespace::Main && page.title == "alpha");
+    assert_eq!(page.revision.format.as_deref(), Some("beta"));
+    assert_eq!(page.revision.text, "delta");
+    assert!(parser.parse_into(&mut page).unwrap());
+    assert!(page.namespace == Namespace::Wikipedia && page.title == "epsilon");
+    assert_eq!(page.revision.format, None);
+    assert_eq!(page.revision.text, "zeta");
+    assert!(!parser.parse_into(&mut page).unwrap());
+}
+
+#[test]
+fn filter() {
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .filter(|header| header.namespace == Namespace::Wikipedia)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "epsilon"));
+    assert!(parser.next().is_none());
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .filter(|header| header.title != "epsilon")
+        .parse_history(std::io::BufReader::new(std::io::Cursor::new(HISTORY_DUMP)));
+    let page = parser.next_page().unwrap().unwrap();
+    assert!(page.title == "alpha");
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(page.title == "theta");
+    assert!(matches!(page.next(), Some(Ok(revision)) if revision.text == "iota"));
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn skip_text() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<model>wikitext</model>",
+        r#"<text bytes="10">beta &amp; gamma</text>"#,
+        "<sha1>0000000000000000000000000000000</sha1>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .skip_text(true)
+        .verify_sha1(true)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    let page = parser.next().unwrap().unwrap();
+    assert_eq!(page.title, "alpha");
+    assert_eq!(page.revision.model.as_deref(), Some("wikitext"));
+    assert_eq!(page.revision.text, "");
+    assert_eq!(page.revision.text_bytes, Some(10));
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn streamed() {
+    use std::io::Read;
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<id>1</id>",
+        "<text>beta &amp; gamma &#x41;</text>",
+        "<sha1>hxy33xa6nkh852zagvbthv43c8mb7fd</sha1>",
+        "</revision>",
+        "<revision>",
+        "<id>2</id>",
+        "<text>delta</text>",
+        "</revision>",
+        "<revision>",
+        "<id>3</id>",
+        "<text>epsilon</text>",
+        "</revision>",
+        "<revision>",
+        "<id>4</id>",
+        r#"<text bytes="0" />"#,
+        "<sha1>phoiac9h4m842xq45sp7s6u21eteeq1</sha1>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>zeta</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<text>eta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    // A small buffer makes entities span several reads from the source.
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .verify_sha1(true)
+        .parse_history(std::io::BufReader::with_capacity(
+            3,
+            std::io::Cursor::new(dump),
+        ));
+    let mut page = parser.next_page().unwrap().unwrap();
+    let mut stream = page.next_streamed().unwrap().unwrap();
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "beta & gamma A");
+    let revision = stream.finish().unwrap();
+    assert_eq!(revision.id, Some(1));
+    assert_eq!(revision.text, "");
+    assert_eq!(
+        revision.sha1.as_deref(),
+        Some("hxy33xa6nkh852zagvbthv43c8mb7fd")
+    );
+    let mut stream = page.next_streamed().unwrap().unwrap();
+    stream.read_exact(&mut [0; 2]).unwrap();
+    assert!(matches!(page.next(), Some(Ok(revision)) if revision.text == "epsilon"));
+    let mut stream = page.next_streamed().unwrap().unwrap();
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "");
+    assert_eq!(stream.finish().unwrap().id, Some(4));
+    assert!(page.next_streamed().is_none());
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(page.title == "zeta");
+    page.next_streamed().unwrap().unwrap();
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn limits() {
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .limit(parse_mediawiki_dump_reboot::Limit::Title, Some(5))
+        .limit(parse_mediawiki_dump_reboot::Limit::Text, Some(4))
+        .parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::LimitExceeded(
+            parse_mediawiki_dump_reboot::Limit::Text,
+            _
+        )))
+    ));
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .limit(parse_mediawiki_dump_reboot::Limit::Title, Some(5))
+        .parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "alpha"));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::LimitExceeded(
+            parse_mediawiki_dump_reboot::Limit::Title,
+            _
+        )))
+    ));
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .limit(parse_mediawiki_dump_reboot::Limit::Revisions, Some(1))
+        .parse_history(std::io::BufReader::new(std::io::Cursor::new(HISTORY_DUMP)));
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(matches!(page.next(), Some(Ok(revision)) if revision.text == "beta"));
+    assert!(matches!(
+        page.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::LimitExceeded(
+            parse_mediawiki_dump_reboot::Limit::Revisions,
+            _
+        )))
+    ));
+    assert!(page.next().is_none());
+    let page = parser.next_page().unwrap().unwrap();
+    assert!(page.title == "epsilon");
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+        "<page>",
+        "<title>alpha</title>",
+        "<ns>0</ns>",
+        "<unknown>gamma delta <epsilon>zeta eta</epsilon> theta</unknown>",
+        "<revision><text>beta</text></revision>",
+        "</page>",
+        "<page>",
+        "<title>iota</title>",
+        "<ns>0</ns>",
+        "<revision><comment>kappa lambda</comment><text>mu</text></revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .limit(parse_mediawiki_dump_reboot::Limit::Text, Some(4))
+        .recovery(true)
+        .parse(dump.as_bytes());
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.revision.text == "beta"));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::Skipped(error, _)))
+            if matches!(
+                &*error,
+                parse_mediawiki_dump_reboot::Error::LimitExceeded(
+                    parse_mediawiki_dump_reboot::Limit::Text,
+                    location,
+                ) if location.path == "mediawiki/page/revision/comment"
+            )
+    ));
+    assert!(parser.next().is_none());
+    // Text between elements is skipped before the XML reader reads the next tag, so it never counts as part of a tag.
+    let dump = format!(
+        concat!(
+            r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+            "<page>{}<title>alpha</title>",
+            "<ns>0</ns>",
+            r#"<redirect title="{}" />"#,
+            "<revision><text>beta</text></revision>",
+            "</page>",
+            "</mediawiki>"
+        ),
+        " ".repeat(100_000),
+        "gamma ".repeat(1000)
+    );
+    let builder = || {
+        parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .limit(parse_mediawiki_dump_reboot::Limit::Tag, Some(100))
+            .limit(parse_mediawiki_dump_reboot::Limit::Text, Some(10))
+    };
+    let short = dump.replace("gamma ", "");
+    let mut parser = builder().parse(short.as_bytes());
+    assert!(matches!(parser.next(), Some(Ok(page)) if page.title == "alpha"));
+    assert!(parser.next().is_none());
+    let mut parser = builder().parse(std::io::BufReader::with_capacity(7, dump.as_bytes()));
+    let error = parser.next().unwrap().unwrap_err();
+    assert!(matches!(
+        &error,
+        parse_mediawiki_dump_reboot::Error::LimitExceeded(
+            parse_mediawiki_dump_reboot::Limit::Tag,
+            location,
+        ) if location.offset == dump.find("<redirect").unwrap() as u64 + 100
+            && location.path == "mediawiki/page"
+            && location.page_title.as_deref() == Some("alpha")
+    ));
+    assert!(
+        error
+            .to_string()
+            .starts_with("The length of the tag exceeds the limit")
+    );
+}
+
+#[test]
+fn builder() {
+    let dump = concat!(
+        "<mediawiki>",
+        "<page>",
+        "<title>\n  alpha  \n</title>",
+        "<ns>0</ns>",
+        "<revision>",
+        "<text>\nbeta\n</text>",
+        "<sha1>izpd7ggt0ln78tvoog6pqvu1m7buz51</sha1>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>gamma</title>",
+        "<ns>1</ns>",
+        "<revision>",
+        "<text>delta</text>",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser =
+        parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert!(matches!(
+        parser.next(),
+        Some(Err(parse_mediawiki_dump_reboot::Error::Format(_)))
+    ));
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .filter(|header| header.namespace == Namespace::Main)
+        .lenient_namespaces(true)
+        .limit(parse_mediawiki_dump_reboot::Limit::Title, Some(16))
+        .trim_text(true)
+        .verify_sha1(true)
+        .parse(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert!(matches!(
+        parser.next(),
+        Some(Ok(page)) if page.title == "alpha" && page.revision.text == "beta"
+    ));
+    assert!(parser.next().is_none());
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .lenient_namespaces(true)
+        .skip_text(true)
+        .parse_history(std::io::BufReader::new(std::io::Cursor::new(dump)));
+    assert_eq!(
+        parser.schema_version().unwrap(),
+        parse_mediawiki_dump_reboot::SchemaVersion::V0_11
+    );
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert!(matches!(page.next(), Some(Ok(revision)) if revision.text.is_empty()));
+}
+
+#[test]
+fn decompress() {
+    use parse_mediawiki_dump_reboot::Compression;
+    use std::io::Write;
+    fn titles(source: Box<dyn std::io::BufRead + Send>) -> Vec<String> {
+        parse_mediawiki_dump_reboot::parse(source)
+            .map(|page| page.unwrap().title)
+            .collect()
+    }
+    assert_eq!(Compression::detect(DUMP.as_bytes()), Compression::None);
+    let source = parse_mediawiki_dump_reboot::decompress(std::io::Cursor::new(DUMP)).unwrap();
+    assert_eq!(titles(source), ["alpha", "epsilon"]);
+    let (head, tail) = DUMP.split_at(DUMP.find("<page><ns>4").unwrap());
+    let mut multistream = vec![];
+    for part in [head, tail] {
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::fast());
+        encoder.write_all(part.as_bytes()).unwrap();
+        multistream.extend(encoder.finish().unwrap());
+    }
+    assert_eq!(Compression::detect(&multistream), Compression::Bzip2);
+    let path = std::env::temp_dir().join(format!(
+        "parse_mediawiki_dump_reboot-{}.xml.bz2",
+        std::process::id()
+    ));
+    std::fs::write(&path, &multistream).unwrap();
+    let result = parse_mediawiki_dump_reboot::open(&path);
+    std::fs::remove_file(&path).unwrap();
+    #[cfg(feature = "bzip2")]
+    assert_eq!(
+        result
+            .unwrap()
+            .map(|page| page.unwrap().title)
+            .collect::<Vec<_>>(),
+        ["alpha", "epsilon"]
+    );
+    #[cfg(not(feature = "bzip2"))]
+    assert_eq!(
+        result.err().unwrap().kind(),
+        std::io::ErrorKind::Unsupported
+    );
+    #[cfg(feature = "gzip")]
+    {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::fast());
+        encoder.write_all(DUMP.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(Compression::detect(&compressed), Compression::Gzip);
+        let source =
+            parse_mediawiki_dump_reboot::decompress(std::io::Cursor::new(compressed)).unwrap();
+        assert_eq!(titles(source), ["alpha", "epsilon"]);
+    }
+    #[cfg(feature = "xz")]
+    {
+        let mut encoder = xz2::write::XzEncoder::new(vec![], 1);
+        encoder.write_all(DUMP.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(Compression::detect(&compressed), Compression::Xz);
+        let source =
+            parse_mediawiki_dump_reboot::decompress(std::io::Cursor::new(compressed)).unwrap();
+        assert_eq!(titles(source), ["alpha", "epsilon"]);
+    }
+    #[cfg(feature = "zstd")]
+    {
+        let compressed = zstd::encode_all(DUMP.as_bytes(), 1).unwrap();
+        assert_eq!(Compression::detect(&compressed), Compression::Zstd);
+        let source =
+            parse_mediawiki_dump_reboot::decompress(std::io::Cursor::new(compressed)).unwrap();
+        assert_eq!(titles(source), ["alpha", "epsilon"]);
+    }
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn multistream() {
+    use std::io::Write;
+    let streams = [
+        concat!(
+            r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+            "<siteinfo><sitename>alpha</sitename></siteinfo>"
+        ),
+        concat!(
+            "<page><title>beta</title><ns>0</ns><id>1</id><revision><text>gamma</text></revision></page>",
+            "<page><title>delta &amp; epsilon</title><ns>0</ns><id>2</id><revision><text>zeta</text></revision></page>"
+        ),
+        "<page><title>eta</title><ns>0</ns><id>3</id><revision><text>theta</text></revision></page>",
+        "</mediawiki>",
+    ];
+    let mut dump = vec![];
+    let mut offsets = vec![];
+    for stream in streams {
+        offsets.push(dump.len());
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::fast());
+        encoder.write_all(stream.as_bytes()).unwrap();
+        dump.extend(encoder.finish().unwrap());
+    }
+    let index = format!(
+        "{0}:1:beta\n{0}:2:delta &amp; epsilon\n{1}:3:eta\n",
+        offsets[1], offsets[2]
+    );
+    let index =
+        parse_mediawiki_dump_reboot::MultistreamIndex::read(std::io::Cursor::new(index)).unwrap();
+    assert_eq!(index.offsets(), [offsets[1] as u64, offsets[2] as u64]);
+    assert_eq!(index.offset_by_id(3), Some(offsets[2] as u64));
+    assert_eq!(
+        index.offset_by_title("delta & epsilon"),
+        Some(offsets[1] as u64)
+    );
+    let mut dump =
+        parse_mediawiki_dump_reboot::MultistreamDump::new(std::io::Cursor::new(dump), index)
+            .unwrap();
+    let page = dump.page_by_title("delta & epsilon").unwrap().unwrap();
+    assert_eq!((page.id, page.revision.text.as_str()), (Some(2), "zeta"));
+    let page = dump.page_by_id(3).unwrap().unwrap();
+    assert_eq!(page.title, "eta");
+    assert!(dump.page_by_id(4).unwrap().is_none());
+    let mut parser = dump.block(offsets[1] as u64).unwrap();
+    assert_eq!(
+        parser.site_info().unwrap().unwrap().sitename.as_deref(),
+        Some("alpha")
+    );
+    assert_eq!(
+        parser.map(|page| page.unwrap().title).collect::<Vec<_>>(),
+        ["beta", "delta & epsilon"]
+    );
+    #[cfg(feature = "rayon")]
+    {
+        assert_eq!(
+            dump.par_pages()
+                .map(|page| page.unwrap().title)
+                .collect::<Vec<_>>(),
+            ["beta", "delta & epsilon", "eta"]
+        );
+        let mut titles = dump
+            .par_pages_unordered()
+            .map(|page| page.unwrap().title)
+            .collect::<Vec<_>>();
+        titles.sort();
+        assert_eq!(titles, ["beta", "delta & epsilon", "eta"]);
+    }
+    assert!(matches!(
+        parse_mediawiki_dump_reboot::MultistreamIndex::read(std::io::Cursor::new("1:beta\n")),
+        Err(parse_mediawiki_dump_reboot::Error::Format(location)) if location.line == 1
+    ));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_pages() {
+    use std::io::Write;
+    struct Source {
+        cursor: std::io::Cursor<Vec<u8>>,
+        read: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    }
+    impl std::io::Read for Source {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = self.cursor.read(buf)?;
+            self.read
+                .fetch_max(self.cursor.position(), std::sync::atomic::Ordering::Relaxed);
+            Ok(length)
+        }
+    }
+    impl std::io::BufRead for Source {
+        fn consume(&mut self, amount: usize) {
+            self.cursor.consume(amount);
+            self.read
+                .fetch_max(self.cursor.position(), std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+            self.cursor.fill_buf()
+        }
+    }
+    impl std::io::Seek for Source {
+        fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.cursor.seek(position)
+        }
+    }
+    // The first stream takes much longer to parse than the following ones, which are finished before it.
+    let page = |id| {
+        format!(
+            "<page><title>{id}</title><ns>0</ns><id>{id}</id><revision><text>alpha</text></revision></page>"
+        )
+    };
+    let mut streams = vec![
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#.to_string(),
+        (0..5000).map(page).collect(),
+    ];
+    streams.extend((5000..5040).map(page));
+    streams.push("</mediawiki>".to_string());
+    let mut dump = vec![];
+    let mut offsets = vec![];
+    for stream in &streams {
+        offsets.push(dump.len() as u64);
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::fast());
+        encoder.write_all(stream.as_bytes()).unwrap();
+        dump.extend(encoder.finish().unwrap());
+    }
+    let index = offsets[2..offsets.len() - 1]
+        .iter()
+        .zip(5000..)
+        .map(|(offset, id)| format!("{offset}:{id}:{id}\n"))
+        .collect::<String>();
+    let index = parse_mediawiki_dump_reboot::MultistreamIndex::read(std::io::Cursor::new(format!(
+        "{}:0:0\n{index}",
+        offsets[1]
+    )))
+    .unwrap();
+    let read = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let mut dump = parse_mediawiki_dump_reboot::MultistreamDump::new(
+        Source {
+            cursor: std::io::Cursor::new(dump),
+            read: read.clone(),
+        },
+        index,
+    )
+    .unwrap();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        let mut pages = dump.par_pages();
+        assert_eq!(pages.next().unwrap().unwrap().id, Some(0));
+        // Two streams per thread are read ahead, including the ones already parsed.
+        assert!(read.load(std::sync::atomic::Ordering::Relaxed) <= offsets[5]);
+        let ids = pages.map(|page| page.unwrap().id.unwrap());
+        assert!(ids.eq(1..5040));
+    });
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn parse_async() {
+    use futures::StreamExt;
+    futures::executor::block_on(async {
+        let source = tokio::io::BufReader::with_capacity(3, DUMP.as_bytes());
+        let pages = parse_mediawiki_dump_reboot::parse_async(source)
+            .into_stream()
+            .collect::<Vec<_>>()
+            .await;
+        let expected =
+            parse_mediawiki_dump_reboot::parse(std::io::BufReader::new(std::io::Cursor::new(DUMP)));
+        assert_eq!(pages.len(), 2);
+        for (page, expected) in pages.into_iter().zip(expected) {
+            let (page, expected) = (page.unwrap(), expected.unwrap());
+            assert_eq!(page.namespace, expected.namespace);
+            assert_eq!(page.title, expected.title);
+            assert_eq!(page.revision.format, expected.revision.format);
+            assert_eq!(page.revision.text, expected.revision.text);
+        }
+        let dump = concat!(
+            r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+            "<page><title>alpha</title><ns>0</ns><revision><text>beta</text></revision></page>",
+            "<page><title>gamma</title></ns></page>"
+        );
+        let mut parser = parse_mediawiki_dump_reboot::parse_async(dump.as_bytes());
+        assert!(matches!(
+            parser.next_page().await,
+            Some(Ok(page)) if page.title == "alpha"
+        ));
+        assert!(matches!(parser.next_page().await, Some(Err(_))));
+        assert!(parser.next_page().await.is_none());
+        let expected = parse_mediawiki_dump_reboot::parse(dump.as_bytes())
+            .find_map(Result::err)
+            .unwrap();
+        let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .filter(|header| header.title != "alpha")
+            .parse_async(tokio::io::BufReader::with_capacity(5, dump.as_bytes()));
+        let error = parser.next_page().await.unwrap().unwrap_err();
+        assert_eq!(error.location(), expected.location());
+        assert_eq!(
+            error.location().unwrap().page_title.as_deref(),
+            Some("gamma")
+        );
+        assert!(parser.next_page().await.is_none());
+        // The stream waits for each byte written on the same task, which only works without blocking.
+        let (mut writer, reader) = tokio::io::duplex(1);
+        let parser = parse_mediawiki_dump_reboot::parse_async(tokio::io::BufReader::new(reader));
+        fn assert_send(_: &impl Send) {}
+        let pages = parser.into_stream().map(Result::unwrap).collect::<Vec<_>>();
+        assert_send(&pages);
+        let (pages, written) = futures::join!(pages, async {
+            tokio::io::AsyncWriteExt::write_all(&mut writer, DUMP.as_bytes()).await?;
+            tokio::io::AsyncWriteExt::shutdown(&mut writer).await
+        });
+        written.unwrap();
+        assert_eq!(
+            pages.iter().map(|page| &page.title[..]).collect::<Vec<_>>(),
+            ["alpha", "epsilon"]
+        );
+    });
+}
+
+#[test]
+fn writer() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">"#,
+        "<siteinfo>",
+        "<sitename>alpha</sitename>",
+        "<dbname>alphawiki</dbname>",
+        "<case>first-letter</case>",
+        "<namespaces>",
+        r#"<namespace key="0" case="first-letter" />"#,
+        r#"<namespace key="4" case="case-sensitive">Alpha &amp; beta</namespace>"#,
+        "</namespaces>",
+        "</siteinfo>",
+        "<page>",
+        "<title>gamma &lt;delta&gt;</title>",
+        "<ns>0</ns>",
+        "<id>1</id>",
+        r#"<redirect title="epsilon &quot;zeta&quot;" />"#,
+        "<restrictions>edit=sysop</restrictions>",
+        "<revision>",
+        "<id>2</id>",
+        "<parentid>1</parentid>",
+        "<timestamp>2001-01-15T13:15:00Z</timestamp>",
+        "<contributor><username>eta</username><id>3</id></contributor>",
+        "<minor />",
+        "<comment>theta &amp; iota</comment>",
+        "<origin>2</origin>",
+        "<model>wikitext</model>",
+        "<format>text/x-wiki</format>",
+        r#"<text bytes="14" xml:space="preserve">  kappa &lt;&amp;&gt;
+</text>"#,
+        "<content>",
+        "<role>mediainfo</role>",
+        "<origin>2</origin>",
+        "<model>wikibase-mediainfo</model>",
+        "<format>application/json</format>",
+        r#"<text bytes="2">{}</text>"#,
+        "</content>",
+        "<sha1>lambda</sha1>",
+        "</revision>",
+        "</page>",
+        "<page>",
+        "<title>Alpha &amp; beta:mu</title>",
+        "<ns>4</ns>",
+        "<revision>",
+        r#"<contributor deleted="deleted" />"#,
+        r#"<comment deleted="deleted" />"#,
+        r#"<text bytes="3" deleted="deleted" />"#,
+        "</revision>",
+        "<revision>",
+        "<contributor><ip>127.0.0.1</ip></contributor>",
+        "<text />",
+        "</revision>",
+        "</page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(dump.as_bytes());
+    let site_info = format!("{:?}", parser.site_info().unwrap().unwrap());
+    let mut writer = parse_mediawiki_dump_reboot::Writer::new(
+        vec![],
+        parser.schema_version().unwrap(),
+        parser.site_info().unwrap(),
+    )
+    .unwrap();
+    let mut expected = vec![];
+    while let Some(page) = parser.next_page() {
+        let mut page = page.unwrap();
+        writer
+            .write_page_start(&parse_mediawiki_dump_reboot::PageHeader {
+                id: page.id,
+                namespace: page.namespace,
+                redirect: page.redirect.as_deref(),
+                restrictions: page.restrictions.as_deref(),
+                title: page.title.as_str(),
+            })
+            .unwrap();
+        for revision in &mut page {
+            let revision = revision.unwrap();
+            writer.write_revision(&revision).unwrap();
+            expected.push(format!("{:?}", revision));
+        }
+        writer.write_page_end().unwrap();
+    }
+    let output = writer.finish().unwrap();
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(&output[..]);
+    assert_eq!(
+        format!("{:?}", parser.site_info().unwrap().unwrap()),
+        site_info
+    );
+    let mut actual = vec![];
+    let mut titles = vec![];
+    while let Some(page) = parser.next_page() {
+        let mut page = page.unwrap();
+        titles.push((
+            page.title.clone(),
+            page.redirect.clone(),
+            page.restrictions.clone(),
+        ));
+        for revision in &mut page {
+            actual.push(format!("{:?}", revision.unwrap()));
+        }
+    }
+    assert_eq!(actual, expected);
+    assert_eq!(
+        titles,
+        [
+            (
+                "gamma <delta>".to_string(),
+                Some("epsilon \"zeta\"".to_string()),
+                Some("edit=sysop".to_string())
+            ),
+            ("Alpha & beta:mu".to_string(), None, None)
+        ]
+    );
+    let mut parser = parse_mediawiki_dump_reboot::parse(std::io::Cursor::new(DUMP));
+    let page = parser.next().unwrap().unwrap();
+    let mut writer = parse_mediawiki_dump_reboot::Writer::new(
+        vec![],
+        parse_mediawiki_dump_reboot::SchemaVersion::V0_10,
+        None,
+    )
+    .unwrap();
+    writer.write_page(&page).unwrap();
+    let output = writer.finish().unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(r#"<text xml:space="preserve">delta</text>"#));
+    let copy = parse_mediawiki_dump_reboot::parse(output.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(format!("{:?}", copy), format!("{:?}", page));
+}
+
+#[test]
+fn error_location() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "\n<page>\n<title>alpha &amp; beta</title>\n<ns>0</ns>\n<id>1</id>\n<revision>\n",
+        "<timestamp>gamma</timestamp>\n",
+        "</revision>\n</page>\n</mediawiki>"
+    );
+    let error = parse_mediawiki_dump_reboot::parse(dump.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap_err();
+    let location = error.location().unwrap();
+    assert!(matches!(
+        error,
+        parse_mediawiki_dump_reboot::Error::Timestamp(_)
+    ));
+    assert_eq!(
+        location,
+        &parse_mediawiki_dump_reboot::Location {
+            column: 17,
+            line: 7,
+            offset: dump.find("</timestamp>").unwrap() as u64,
+            page_id: Some(1),
+            page_title: Some("alpha & beta".to_string()),
+            path: "mediawiki/page/revision/timestamp".to_string(),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Invalid timestamp at line 7, column 17 (offset {}) in mediawiki/page/revision/timestamp of the page \"alpha & beta\"",
+            location.offset
+        )
+    );
+    let error = parse_mediawiki_dump_reboot::parse(
+        concat!(
+            r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+            "<page><title>alpha</title><ns>zero</ns></page>",
+            "</mediawiki>"
+        )
+        .as_bytes(),
+    )
+    .next()
+    .unwrap()
+    .unwrap_err();
+    assert!(matches!(
+        error,
+        parse_mediawiki_dump_reboot::Error::Format(location)
+            if location.line == 1
+                && location.path == "mediawiki/page/ns"
+                && location.page_id.is_none()
+                && location.page_title.as_deref() == Some("alpha")
+    ));
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "\n<page>\n<title>alpha</title>\n<ns>0</ns>\n<revision>\n",
+        "<text>beta</text>\n</revisio>\n</page>\n</mediawiki>"
+    );
+    let error = parse_mediawiki_dump_reboot::parse(dump.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(
+        &error,
+        parse_mediawiki_dump_reboot::Error::XmlReader(_, location)
+            if location.line == 7
+                && location.path == "mediawiki/page/revision"
+                && location.page_title.as_deref() == Some("alpha")
+    ));
+    assert!(
+        error
+            .to_string()
+            .ends_with("in mediawiki/page/revision of the page \"alpha\"")
+    );
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page><title>alpha</title><ns>0</ns><id>1</id><revision>",
+        "<text>beta &nbsp;</text>",
+        "</revision></page>",
+        "<page><title>gamma</title><ns>0</ns><id>2</id><revision>",
+        "<text>delta</text><sha1>izpd7ggt0ln78tvoog6pqvu1m7buz51</sha1>",
+        "</revision></page>",
+        "</mediawiki>"
+    );
+    let check = |error: parse_mediawiki_dump_reboot::Error| match error {
+        parse_mediawiki_dump_reboot::Error::Escape(_, location) => {
+            assert_eq!(location.offset, dump.find("beta &nbsp;").unwrap() as u64);
+            assert_eq!(location.page_id, Some(1));
+            assert_eq!(location.page_title.as_deref(), Some("alpha"));
+            assert_eq!(location.path, "mediawiki/page/revision/text");
+        }
+        parse_mediawiki_dump_reboot::Error::Sha1Mismatch(None, location) => {
+            assert_eq!(location.offset, dump.find("izpd7").unwrap() as u64);
+            assert_eq!(location.page_id, Some(2));
+            assert_eq!(location.page_title.as_deref(), Some("gamma"));
+            assert_eq!(location.path, "mediawiki/page/revision/sha1");
+        }
+        error => panic!("unexpected error {error}"),
+    };
+    let parse = || {
+        parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .verify_sha1(true)
+            .parse(dump.as_bytes())
+    };
+    let mut parser = parse();
+    check(parser.next().unwrap().unwrap_err());
+    check(parser.next().unwrap().unwrap_err());
+    let mut parser = parse();
+    check(parser.next_borrowed().unwrap().unwrap_err());
+    check(parser.next_borrowed().unwrap().unwrap_err());
+    let mut parser = parse();
+    let mut page = parse_mediawiki_dump_reboot::Page::default();
+    check(parser.parse_into(&mut page).unwrap_err());
+    check(parser.parse_into(&mut page).unwrap_err());
+}
+
+#[test]
+fn recovery() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page><title>alpha</title><ns>0</ns><revision><text>beta</text></revision></page>",
+        "<page><title>gamma</title><ns>zero</ns><revision><text>delta</text></revision></page>",
+        "<page><title>epsilon</title><ns>0</ns><revision><text>zeta</txet></revision></page>",
+        "<page>\n<title>eta</title><ns>0</ns><revision><text>theta</text></revision></page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .recovery(true)
+        .parse(dump.as_bytes());
+    assert_eq!(parser.next().unwrap().unwrap().title, "alpha");
+    let error = parser.next().unwrap().unwrap_err();
+    let start = dump.find("zero<").unwrap() as u64 + 4;
+    let end = dump.find("<page><title>epsilon").unwrap() as u64;
+    assert!(matches!(
+        &error,
+        parse_mediawiki_dump_reboot::Error::Skipped(error, range)
+            if matches!(**error, parse_mediawiki_dump_reboot::Error::Format(_))
+                && *range == (start..end)
+    ));
+    assert_eq!(
+        error.location().unwrap().page_title.as_deref(),
+        Some("gamma")
+    );
+    assert!(matches!(
+        parser.next().unwrap().unwrap_err(),
+        parse_mediawiki_dump_reboot::Error::Skipped(error, range)
+            if matches!(*error, parse_mediawiki_dump_reboot::Error::XmlReader(..))
+                && range.end == dump.find("<page>\n").unwrap() as u64
+    ));
+    let page = parser.next().unwrap().unwrap();
+    assert_eq!(page.title, "eta");
+    assert_eq!(page.revision.text, "theta");
+    assert!(parser.next().is_none());
+    let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+    assert_eq!(parser.next().unwrap().unwrap().title, "alpha");
+    assert!(matches!(
+        parser.next().unwrap().unwrap_err(),
+        parse_mediawiki_dump_reboot::Error::Format(_)
+    ));
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .recovery(true)
+        .parse_history(
+            concat!(
+                r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+                "<page><title>alpha</title><ns>0</ns>",
+                "<revision><id>1</id><text /></revision>",
+                "<revision><id>two</id><text /></revision>",
+                "<revision><id>3</id><text /></revision></page>",
+                "<page><title>beta</title><ns>0</ns><revision><id>4</id><text /></revision></page>",
+                "</mediawiki>"
+            )
+            .as_bytes(),
+        );
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert_eq!(page.next().unwrap().unwrap().id, Some(1));
+    assert!(matches!(
+        page.next().unwrap().unwrap_err(),
+        parse_mediawiki_dump_reboot::Error::Skipped(..)
+    ));
+    assert!(page.next().is_none());
+    let mut page = parser.next_page().unwrap().unwrap();
+    assert_eq!(page.title, "beta");
+    assert_eq!(page.next().unwrap().unwrap().id, Some(4));
+    assert!(page.next().is_none());
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn truncated() {
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page><title>alpha</title><ns>0</ns><revision><id>1</id><text>beta</text></revision>",
+        "<revision><id>2</id><text>gam"
+    );
+    for recovery in [false, true] {
+        let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .recovery(recovery)
+            .parse(dump.as_bytes());
+        assert!(parser.next().unwrap().is_err());
+        assert!(parser.next().is_none());
+        let dump = &dump[..dump.find("<revision><id>2").unwrap()];
+        let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .recovery(recovery)
+            .parse(dump.as_bytes());
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!(
+            matches!(error, parse_mediawiki_dump_reboot::Error::Skipped(..)),
+            recovery
+        );
+        assert!(parser.next().is_none());
+        assert!(parser.next().is_none());
+        let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+        let mut page = Default::default();
+        assert!(parser.parse_into(&mut page).is_err());
+        assert!(!parser.parse_into(&mut page).unwrap());
+        let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+            .recovery(recovery)
+            .parse_history(dump.as_bytes());
+        let mut page = parser.next_page().unwrap().unwrap();
+        assert_eq!(page.next().unwrap().unwrap().id, Some(1));
+        assert!(page.next().unwrap().is_err());
+        assert!(page.next().is_none());
+        assert!(parser.next_page().is_none());
+    }
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(dump.as_bytes());
+    let mut page = parser.next_page().unwrap().unwrap();
+    page.next().unwrap().unwrap();
+    assert!(page.next().unwrap().is_err());
+    assert!(page.next().is_none());
+    assert!(parser.next_page().is_none());
+}
+
+#[test]
+fn unknown_entities() {
+    use std::io::Read;
+    let dump = concat!(
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.10/">"#,
+        "<page><title>alpha</title><ns>0</ns><revision>",
+        "<text>beta &nbsp; &amp; gamma &amp &lt; &#xZZ; &#x41;</text>",
+        "</revision></page>",
+        "<page><title>delta</title><ns>0</ns><revision><text>epsilon</text></revision></page>",
+        "</mediawiki>"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::parse(dump.as_bytes());
+    let error = parser.next().unwrap().unwrap_err();
+    assert!(matches!(
+        &error,
+        parse_mediawiki_dump_reboot::Error::Escape(_, location)
+            if location.offset == dump.find("beta &nbsp;").unwrap() as u64
+                && location.page_title.as_deref() == Some("alpha")
+    ));
+    assert!(error.to_string().starts_with("Invalid entity at line 1"));
+    assert_eq!(parser.next().unwrap().unwrap().title, "delta");
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .keep_unknown_entities(true)
+        .parse(dump.as_bytes());
+    assert_eq!(
+        parser.next().unwrap().unwrap().revision.text,
+        "beta &nbsp; & gamma &amp < &#xZZ; A"
+    );
+    let mut parser = parse_mediawiki_dump_reboot::parse_history(std::io::BufReader::with_capacity(
+        3,
+        std::io::Cursor::new(dump),
+    ));
+    let mut page = parser.next_page().unwrap().unwrap();
+    let mut stream = page.next_streamed().unwrap().unwrap();
+    let error = stream.read_to_string(&mut String::new()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    let mut parser = parse_mediawiki_dump_reboot::ParserBuilder::new()
+        .keep_unknown_entities(true)
+        .parse_history(std::io::BufReader::with_capacity(
+            3,
+            std::io::Cursor::new(dump),
+        ));
+    let mut page = parser.next_page().unwrap().unwrap();
+    let mut stream = page.next_streamed().unwrap().unwrap();
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "beta &nbsp; & gamma &amp < &#xZZ; A");
+}